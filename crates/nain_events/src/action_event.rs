@@ -0,0 +1,49 @@
+use crate::event::{
+    event_category_flags, event_handled, event_type, Event, EventCategory, EventType,
+};
+use std::fmt;
+
+// Action event
+
+/// A named input action changed, as mapped from raw input by the game's
+/// bindings. Buttons go between `0.0` and `1.0`, axes anywhere in
+/// `-1.0..=1.0`.
+pub struct ActionEvent {
+    action: String,
+    value: f32,
+    handled: bool,
+}
+
+impl Event for ActionEvent {
+    event_category_flags!(INPUT);
+    event_type!(EventType::Action);
+    event_handled!();
+}
+
+impl fmt::Display for ActionEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "ActionEvent: {} ({})", self.action, self.value)
+    }
+}
+
+impl ActionEvent {
+    pub fn new<T: Into<String>>(action: T, value: f32) -> Self {
+        Self {
+            action: action.into(),
+            value,
+            handled: false,
+        }
+    }
+
+    pub fn get_action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.value != 0.0
+    }
+}
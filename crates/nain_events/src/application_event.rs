@@ -1,4 +1,6 @@
-use crate::event::{event_category_flags, event_type, Event, EventCategory, EventType};
+use crate::event::{
+    event_category_flags, event_handled, event_type, Event, EventCategory, EventType,
+};
 use std::fmt;
 
 // Window resize event
@@ -6,11 +8,13 @@ use std::fmt;
 pub struct WindowResizeEvent {
     width: u32,
     height: u32,
+    handled: bool,
 }
 
 impl Event for WindowResizeEvent {
-    event_category_flags!(EventCategory::Application);
+    event_category_flags!(APPLICATION);
     event_type!(EventType::WindowResize);
+    event_handled!();
 }
 
 impl fmt::Display for WindowResizeEvent {
@@ -25,7 +29,11 @@ impl fmt::Display for WindowResizeEvent {
 
 impl WindowResizeEvent {
     pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+        Self {
+            width,
+            height,
+            handled: false,
+        }
     }
 
     pub fn get_width(&self) -> u32 {
@@ -39,11 +47,15 @@ impl WindowResizeEvent {
 
 // Window close event
 
-pub struct WindowCloseEvent;
+#[derive(Default)]
+pub struct WindowCloseEvent {
+    handled: bool,
+}
 
 impl Event for WindowCloseEvent {
-    event_category_flags!(EventCategory::Application);
+    event_category_flags!(APPLICATION);
     event_type!(EventType::WindowClose);
+    event_handled!();
 }
 
 impl fmt::Display for WindowCloseEvent {
@@ -52,13 +64,111 @@ impl fmt::Display for WindowCloseEvent {
     }
 }
 
+impl WindowCloseEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// Window moved event
+
+pub struct WindowMovedEvent {
+    x: i32,
+    y: i32,
+    handled: bool,
+}
+
+impl Event for WindowMovedEvent {
+    event_category_flags!(APPLICATION);
+    event_type!(EventType::WindowMoved);
+    event_handled!();
+}
+
+impl fmt::Display for WindowMovedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "WindowMovedEvent: {}, {}", self.x, self.y)
+    }
+}
+
+impl WindowMovedEvent {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self {
+            x,
+            y,
+            handled: false,
+        }
+    }
+
+    pub fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn get_y(&self) -> i32 {
+        self.y
+    }
+}
+
+// Window focus event
+
+#[derive(Default)]
+pub struct WindowFocusEvent {
+    handled: bool,
+}
+
+impl Event for WindowFocusEvent {
+    event_category_flags!(APPLICATION);
+    event_type!(EventType::WindowFocus);
+    event_handled!();
+}
+
+impl fmt::Display for WindowFocusEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "WindowFocusEvent")
+    }
+}
+
+impl WindowFocusEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// Window lost focus event
+
+#[derive(Default)]
+pub struct WindowLostFocusEvent {
+    handled: bool,
+}
+
+impl Event for WindowLostFocusEvent {
+    event_category_flags!(APPLICATION);
+    event_type!(EventType::WindowLostFocus);
+    event_handled!();
+}
+
+impl fmt::Display for WindowLostFocusEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "WindowLostFocusEvent")
+    }
+}
+
+impl WindowLostFocusEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 // App tick event
 
-pub struct AppTickEvent;
+#[derive(Default)]
+pub struct AppTickEvent {
+    handled: bool,
+}
 
 impl Event for AppTickEvent {
-    event_category_flags!(EventCategory::Application);
+    event_category_flags!(APPLICATION);
     event_type!(EventType::AppTick);
+    event_handled!();
 }
 
 impl fmt::Display for AppTickEvent {
@@ -67,13 +177,23 @@ impl fmt::Display for AppTickEvent {
     }
 }
 
+impl AppTickEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 // App update event
 
-pub struct AppUpdateEvent;
+#[derive(Default)]
+pub struct AppUpdateEvent {
+    handled: bool,
+}
 
 impl Event for AppUpdateEvent {
-    event_category_flags!(EventCategory::Application);
+    event_category_flags!(APPLICATION);
     event_type!(EventType::AppUpdate);
+    event_handled!();
 }
 
 impl fmt::Display for AppUpdateEvent {
@@ -82,13 +202,23 @@ impl fmt::Display for AppUpdateEvent {
     }
 }
 
+impl AppUpdateEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 // App render event
 
-pub struct AppRenderEvent;
+#[derive(Default)]
+pub struct AppRenderEvent {
+    handled: bool,
+}
 
 impl Event for AppRenderEvent {
-    event_category_flags!(EventCategory::Application);
+    event_category_flags!(APPLICATION);
     event_type!(EventType::AppRender);
+    event_handled!();
 }
 
 impl fmt::Display for AppRenderEvent {
@@ -96,3 +226,9 @@ impl fmt::Display for AppRenderEvent {
         write!(formater, "AppRenderEvent")
     }
 }
+
+impl AppRenderEvent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
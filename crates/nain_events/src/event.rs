@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::fmt::Display;
 
 pub enum EventType {
@@ -12,33 +13,96 @@ pub enum EventType {
     AppRender,
     KeyPressed,
     KeyReleased,
+    KeyTyped,
+    TextInput,
     MouseButtonPressed,
     MouseButtonReleased,
     MouseMoved,
     MouseScrolled,
+    GamepadConnected,
+    GamepadDisconnected,
+    GamepadButtonPressed,
+    GamepadButtonReleased,
+    GamepadAxisMoved,
+    Action,
 }
 
-#[derive(PartialEq)]
-pub enum EventCategory {
-    None,
-    Application,
-    Input,
-    Keyboard,
-    Mouse,
-    MouseButton,
+bitflags! {
+    /// Categories an event belongs to. An event can be in several at once, e.g.
+    /// key events are both `INPUT` and `KEYBOARD`.
+    pub struct EventCategory: u32 {
+        const NONE = 0;
+        const APPLICATION = 1 << 0;
+        const INPUT = 1 << 1;
+        const KEYBOARD = 1 << 2;
+        const MOUSE = 1 << 3;
+        const MOUSE_BUTTON = 1 << 4;
+        const GAMEPAD = 1 << 5;
+    }
 }
 
-pub trait Event: Display + 'static {
+/// Returned by event handlers to decide whether lower priority handlers still
+/// receive the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    Continue,
+    Stop,
+}
+
+impl From<()> for Propagation {
+    fn from(_: ()) -> Self {
+        Propagation::Continue
+    }
+}
+
+pub trait Event: Any + Display {
     fn get_type(&self) -> EventType;
     fn get_name(&self) -> String;
     fn get_category_flags(&self) -> EventCategory;
+    fn is_handled(&self) -> bool;
+    fn set_handled(&mut self, handled: bool);
+    /// The concrete event, for downcasting.
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
     fn is_in_category(&self, category: EventCategory) -> bool {
-        self.get_category_flags() == category
+        self.get_category_flags().intersects(category)
+    }
+}
+
+/// Converts between concrete events and `dyn Event`, so the bus can treat
+/// sized events and trait objects alike.
+pub trait AsEvent {
+    fn as_event_mut(&mut self) -> &mut dyn Event;
+    fn from_event_mut(event: &mut dyn Event) -> Option<&mut Self>;
+}
+
+impl<T: Event> AsEvent for T {
+    fn as_event_mut(&mut self) -> &mut dyn Event {
+        self
+    }
+
+    fn from_event_mut(event: &mut dyn Event) -> Option<&mut Self> {
+        event.as_any_mut().downcast_mut::<T>()
+    }
+}
+
+impl AsEvent for dyn Event {
+    fn as_event_mut(&mut self) -> &mut dyn Event {
+        self
+    }
+
+    fn from_event_mut(event: &mut dyn Event) -> Option<&mut Self> {
+        Some(event)
     }
 }
 
 #[macro_export]
 macro_rules! event_category_flags {
+    ($($category:ident)|+) => {
+        fn get_category_flags(&self) -> EventCategory {
+            $(EventCategory::$category)|+
+        }
+    };
     ($category:expr) => {
         fn get_category_flags(&self) -> EventCategory {
             $category
@@ -58,7 +122,30 @@ macro_rules! event_type {
         fn get_name(&self) -> String {
             stringify!($type).to_string()
         }
+
+        fn as_any(&self) -> &dyn ::std::any::Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+            self
+        }
     };
 }
 
 pub(crate) use event_type;
+
+#[macro_export]
+macro_rules! event_handled {
+    () => {
+        fn is_handled(&self) -> bool {
+            self.handled
+        }
+
+        fn set_handled(&mut self, handled: bool) {
+            self.handled = handled;
+        }
+    };
+}
+
+pub(crate) use event_handled;
@@ -1,8 +1,12 @@
-use crate::event::Event;
-use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use crate::event::{AsEvent, Event, EventCategory, Propagation};
+use std::any::{type_name, Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 
 use nain_log as log;
 
@@ -20,22 +24,136 @@ macro_rules! dispatch_event {
     };
 }
 
+type EventIdMap = HashMap<usize, Box<dyn HandlerList>>;
+type EventHandler = Arc<dyn Fn(&mut dyn Event) -> Propagation + Send + Sync + 'static>;
+type EventQueue = VecDeque<QueuedEvent>;
+type QueuedDispatch = Box<dyn FnOnce(&str) -> Result<bool, EventBusError> + Send>;
+
 lazy_static! {
     static ref EVENT_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    static ref HANDLER_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
     static ref EVENT_ID_MAP: Mutex<HashMap<TypeId, usize>> = Mutex::new(HashMap::new());
-    static ref EVENT_HANDLER_MAP: RwLock<HashMap<String, RwLock<HashMap<usize, Box<dyn Any + Send + Sync + 'static>>>>> =
+    static ref EVENT_HANDLER_MAP: RwLock<HashMap<String, RwLock<EventIdMap>>> =
         RwLock::new(HashMap::new());
+    static ref EVENT_QUEUE_MAP: Mutex<HashMap<String, EventQueue>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EventBusError {
+    InvalidBus(String),
+    HandlerTypeMismatch { bus: String, event: String },
+}
+
+impl fmt::Display for EventBusError {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventBusError::InvalidBus(bus) => write!(formater, "Invalid event bus: '{}'", bus),
+            EventBusError::HandlerTypeMismatch { bus, event } => write!(
+                formater,
+                "Handlers registered on bus '{}' do not match event type '{}'",
+                bus, event
+            ),
+        }
+    }
 }
 
+impl Error for EventBusError {}
+
 pub struct EventBus {
     name: String,
 }
 
-struct EventHandlers<T: Event + ?Sized>(Vec<(usize, Box<dyn Fn(&mut T) + Send + Sync + 'static>)>);
+struct QueuedEvent {
+    queued_at: Instant,
+    dispatch: QueuedDispatch,
+}
+
+/// Identifies a single handler registered with `subscribe_event`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionHandle {
+    bus: String,
+    event_id: usize,
+    handler_id: usize,
+}
+
+impl SubscriptionHandle {
+    pub fn get_bus(&self) -> &str {
+        &self.bus
+    }
+
+    /// Removes the handler, returning `false` if it was already gone.
+    pub fn unsubscribe(self) -> bool {
+        unsubscribe_event(&self)
+    }
+
+    /// Turns the handle into a guard that unsubscribes when dropped.
+    pub fn into_guard(self) -> SubscriptionGuard {
+        SubscriptionGuard { handle: Some(self) }
+    }
+}
+
+/// Unsubscribes its handler when dropped.
+#[derive(Debug)]
+pub struct SubscriptionGuard {
+    handle: Option<SubscriptionHandle>,
+}
+
+impl SubscriptionGuard {
+    /// Gives back the handle without unsubscribing.
+    pub fn release(mut self) -> SubscriptionHandle {
+        self.handle
+            .take()
+            .expect("Subscription guard without handle")
+    }
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            unsubscribe_event(&handle);
+        }
+    }
+}
+
+trait HandlerList: Any + Send + Sync {
+    fn event_type(&self) -> TypeId;
+    fn handlers(&self) -> &[(usize, usize, EventHandler)];
+    fn remove(&mut self, handler_id: usize) -> bool;
+    fn len(&self) -> usize;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Handlers for events of type `T`, stored type-erased so they can also be
+/// reached when the event is dispatched as `&mut dyn Event`.
+struct EventHandlers<T: Event + ?Sized>(Vec<(usize, usize, EventHandler)>, PhantomData<fn(&mut T)>);
 
 impl<T: Event + ?Sized> Default for EventHandlers<T> {
     fn default() -> Self {
-        EventHandlers(vec![])
+        EventHandlers(vec![], PhantomData)
+    }
+}
+
+impl<T: Event + ?Sized> HandlerList for EventHandlers<T> {
+    fn event_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn handlers(&self) -> &[(usize, usize, EventHandler)] {
+        &self.0
+    }
+
+    fn remove(&mut self, handler_id: usize) -> bool {
+        let len = self.0.len();
+        self.0.retain(|(_, id, _)| *id != handler_id);
+        self.0.len() != len
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
 }
 
@@ -53,8 +171,69 @@ impl EventBus {
         map.entry(name.clone())
             .or_insert_with(|| RwLock::new(HashMap::new()));
 
+        EVENT_QUEUE_MAP
+            .lock()
+            .expect("Failed to lock event queue map")
+            .insert(name.clone(), VecDeque::new());
+
         EventBus { name }
     }
+
+    pub fn enqueue<T: Event + Send>(&self, event: T) -> Result<(), EventBusError> {
+        enqueue_event(&self.name, event)
+    }
+
+    pub fn flush(&self) -> Result<usize, EventBusError> {
+        flush_events(&self.name)
+    }
+
+    pub fn flush_until(&self, deadline: Instant) -> Result<usize, EventBusError> {
+        flush_events_until(&self.name, deadline)
+    }
+
+    pub fn pending_count(&self) -> usize {
+        EVENT_QUEUE_MAP
+            .lock()
+            .expect("Failed to lock event queue map")
+            .get(&self.name)
+            .map_or(0, |queue| queue.len())
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn unsubscribe(&self, handle: SubscriptionHandle) -> bool {
+        handle.bus == self.name && unsubscribe_event(&handle)
+    }
+
+    pub fn clear_bus(&self) {
+        if let Some(event_id_map) = EVENT_HANDLER_MAP
+            .read()
+            .expect("Failed to get read guard on handler map")
+            .get(&self.name)
+        {
+            event_id_map
+                .write()
+                .expect("Failed to get write guard on event id map")
+                .clear();
+        }
+    }
+
+    pub fn handler_count(&self) -> usize {
+        EVENT_HANDLER_MAP
+            .read()
+            .expect("Failed to get read guard on handler map")
+            .get(&self.name)
+            .map_or(0, |event_id_map| {
+                event_id_map
+                    .read()
+                    .expect("Failed to get read guard on event id map")
+                    .values()
+                    .map(|handlers| handlers.len())
+                    .sum()
+            })
+    }
 }
 
 impl Drop for EventBus {
@@ -63,78 +242,1046 @@ impl Drop for EventBus {
             .write()
             .expect("Failed to get write guard on handler map")
             .remove(&self.name);
+
+        EVENT_QUEUE_MAP
+            .lock()
+            .expect("Failed to lock event queue map")
+            .remove(&self.name);
     }
 }
 
-pub fn dispatch_event<T: Event + ?Sized>(bus: &str, event: &mut T) {
-    let event_id = 1;
-    let map = EVENT_HANDLER_MAP
-        .read()
-        .expect("Failed to get read guard on handler map");
+/// Runs the handlers of `bus` from the highest priority down, stopping as soon
+/// as one of them returns `Propagation::Stop` or marks the event handled.
+/// Handlers for the event's concrete type and category handlers are
+/// interleaved by priority, also when the event is passed as `&mut dyn Event`.
+/// Returns whether the event was consumed.
+///
+/// The handler list is snapshotted before any handler runs and no lock is held
+/// while they do, so handlers may subscribe, unsubscribe or dispatch. Changes
+/// they make take effect from the next dispatch.
+pub fn dispatch_event<T: Event + AsEvent + ?Sized>(
+    bus: &str,
+    event: &mut T,
+) -> Result<bool, EventBusError> {
+    let event = event.as_event_mut();
+    let handlers = snapshot_handlers(bus, event)?;
 
-    if map.contains_key(bus) {
-        let event_id_map = map
-            .get(bus)
-            .unwrap()
-            .read()
-            .expect("Failed to get read guard on event id map");
+    for (_, handler) in handlers.iter().rev() {
+        if event.is_handled() {
+            break;
+        }
 
-        println!("{:?} dispatch_event event_id", event_id);
+        if handler(event) == Propagation::Stop {
+            event.set_handled(true);
+        }
+    }
 
-        if let Some(handlers) = event_id_map.get(&event_id) {
-            let handlers = handlers.downcast_ref::<EventHandlers<T>>().unwrap();
+    Ok(event.is_handled())
+}
 
-            println!("dispatch_event handlers");
+/// Queues `event` to be dispatched on `bus` by the next `flush_events`. Can be
+/// called from any thread, including from inside a handler. Every bus has its
+/// own queue, flushed by whoever owns the bus: an `Application` flushes its
+/// own bus at the end of every frame.
+pub fn enqueue_event<T: Event + Send>(bus: &str, mut event: T) -> Result<(), EventBusError> {
+    let mut queues = EVENT_QUEUE_MAP
+        .lock()
+        .expect("Failed to lock event queue map");
 
-            for handler in handlers.0.iter().rev() {
-                handler.1(event);
-            }
-        }
+    if let Some(queue) = queues.get_mut(bus) {
+        // Taking the timestamp under the lock keeps the queue ordered by time.
+        queue.push_back(QueuedEvent {
+            queued_at: Instant::now(),
+            dispatch: Box::new(move |bus| dispatch_event(bus, &mut event)),
+        });
+
+        Ok(())
     } else {
-        log::warn!("Cannot dispatch event on invalid bus: '{}'", bus);
+        log::warn!("Cannot enqueue event on invalid bus: '{}'", bus);
+        Err(EventBusError::InvalidBus(bus.to_string()))
+    }
+}
+
+/// Dispatches every event queued on `bus` before this call, in the order they
+/// were enqueued. Events enqueued while flushing wait for the next flush.
+/// Returns the number of events dispatched.
+pub fn flush_events(bus: &str) -> Result<usize, EventBusError> {
+    flush_events_until(bus, Instant::now())
+}
+
+/// Like `flush_events`, but only dispatches events queued at or before
+/// `deadline`.
+pub fn flush_events_until(bus: &str, deadline: Instant) -> Result<usize, EventBusError> {
+    let events: Vec<QueuedEvent> = {
+        let mut queues = EVENT_QUEUE_MAP
+            .lock()
+            .expect("Failed to lock event queue map");
+
+        let queue = queues.get_mut(bus).ok_or_else(|| {
+            log::warn!("Cannot flush events on invalid bus: '{}'", bus);
+            EventBusError::InvalidBus(bus.to_string())
+        })?;
+
+        let count = queue
+            .iter()
+            .take_while(|event| event.queued_at <= deadline)
+            .count();
+
+        queue.drain(..count).collect()
+    };
+
+    let count = events.len();
+
+    for event in events {
+        if let Err(error) = (event.dispatch)(bus) {
+            log::error!("Failed to dispatch queued event: {}", error);
+        }
     }
+
+    Ok(count)
 }
 
-pub fn subscribe_event<T: Event + ?Sized, H: Fn(&mut T) + Send + Sync + 'static>(
+/// Registers `handler` for events of type `T` on `bus`. Handlers may return
+/// `()` or a `Propagation`; higher priorities run first.
+pub fn subscribe_event<T, H, R>(
     bus: &str,
     handler: H,
     priority: usize,
-) {
-    let event_id = 1;
+) -> Result<SubscriptionHandle, EventBusError>
+where
+    T: Event + AsEvent + ?Sized,
+    H: Fn(&mut T) -> R + Send + Sync + 'static,
+    R: Into<Propagation>,
+{
+    let event_id = get_event_id::<T>();
     let map = EVENT_HANDLER_MAP
         .read()
         .expect("Failed to get read guard on handler map");
 
-    if map.contains_key(bus) {
-        let mut event_id_map = map
-            .get(bus)
-            .unwrap()
+    if let Some(event_id_map) = map.get(bus) {
+        let mut event_id_map = event_id_map
             .write()
             .expect("Failed to get write guard on event id map");
 
-        println!("{:?} subscribe_event event_id", event_id);
-
         let handlers = event_id_map
             .entry(event_id)
-            .or_insert(Box::new(EventHandlers::<T>::default()))
+            .or_insert_with(|| Box::new(EventHandlers::<T>::default()))
+            .as_any_mut()
             .downcast_mut::<EventHandlers<T>>()
-            .unwrap();
+            .ok_or_else(|| type_mismatch(bus, type_name::<T>()))?;
 
         let pos = match handlers.0.binary_search_by(|probe| probe.0.cmp(&priority)) {
             Ok(pos) => pos,
             Err(pos) => pos,
         };
 
-        handlers.0.insert(pos, (priority, Box::new(handler)));
+        let handler_id = HANDLER_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        handlers.0.insert(
+            pos,
+            (
+                priority,
+                handler_id,
+                Arc::new(
+                    move |event: &mut dyn Event| match T::from_event_mut(event) {
+                        Some(event) => handler(event).into(),
+                        None => Propagation::Continue,
+                    },
+                ),
+            ),
+        );
+
+        Ok(SubscriptionHandle {
+            bus: bus.to_string(),
+            event_id,
+            handler_id,
+        })
     } else {
         log::warn!("Cannot subscribe on invalid bus: '{}'", bus);
+        Err(EventBusError::InvalidBus(bus.to_string()))
+    }
+}
+
+/// Registers `handler` for every event on `bus` that is in any of the given
+/// categories.
+pub fn subscribe_category<H, R>(
+    bus: &str,
+    category: EventCategory,
+    handler: H,
+    priority: usize,
+) -> Result<SubscriptionHandle, EventBusError>
+where
+    H: Fn(&mut dyn Event) -> R + Send + Sync + 'static,
+    R: Into<Propagation>,
+{
+    subscribe_event(
+        bus,
+        move |event: &mut dyn Event| {
+            if event.is_in_category(category) {
+                handler(event).into()
+            } else {
+                Propagation::Continue
+            }
+        },
+        priority,
+    )
+}
+
+/// Removes the handler behind `handle`, returning `false` if the handler or its
+/// bus no longer exists.
+pub fn unsubscribe_event(handle: &SubscriptionHandle) -> bool {
+    let map = EVENT_HANDLER_MAP
+        .read()
+        .expect("Failed to get read guard on handler map");
+
+    if let Some(event_id_map) = map.get(&handle.bus) {
+        let mut event_id_map = event_id_map
+            .write()
+            .expect("Failed to get write guard on event id map");
+
+        event_id_map
+            .get_mut(&handle.event_id)
+            .is_some_and(|handlers| handlers.remove(handle.handler_id))
+    } else {
+        false
     }
 }
 
 fn get_event_id<T: Event + ?Sized>() -> usize {
+    get_event_id_of(TypeId::of::<T>())
+}
+
+fn get_event_id_of(type_id: TypeId) -> usize {
     *EVENT_ID_MAP
         .lock()
         .expect("Failed to lock event id map")
-        .entry(TypeId::of::<T>())
+        .entry(type_id)
         .or_insert_with(|| EVENT_ID_COUNTER.fetch_add(1, Ordering::Relaxed))
 }
+
+/// Collects the handlers `event` should reach, sorted by ascending priority.
+/// At equal priority, handlers for the concrete type run before category
+/// handlers.
+fn snapshot_handlers(
+    bus: &str,
+    event: &dyn Event,
+) -> Result<Vec<(usize, EventHandler)>, EventBusError> {
+    let type_id = event.as_any().type_id();
+    let event_id = get_event_id_of(type_id);
+    let any_event_id = get_event_id::<dyn Event>();
+    let map = EVENT_HANDLER_MAP
+        .read()
+        .expect("Failed to get read guard on handler map");
+
+    if let Some(event_id_map) = map.get(bus) {
+        let event_id_map = event_id_map
+            .read()
+            .expect("Failed to get read guard on event id map");
+
+        let mut snapshot = vec![];
+
+        for id in &[any_event_id, event_id] {
+            if let Some(handlers) = event_id_map.get(id) {
+                if *id == event_id && handlers.event_type() != type_id {
+                    return Err(type_mismatch(bus, &event.get_name()));
+                }
+
+                snapshot.extend(
+                    handlers
+                        .handlers()
+                        .iter()
+                        .map(|(priority, _, handler)| (*priority, handler.clone())),
+                );
+            }
+        }
+
+        snapshot.sort_by_key(|(priority, _)| *priority);
+
+        Ok(snapshot)
+    } else {
+        log::warn!("Cannot dispatch event on invalid bus: '{}'", bus);
+        Err(EventBusError::InvalidBus(bus.to_string()))
+    }
+}
+
+fn type_mismatch(bus: &str, event: &str) -> EventBusError {
+    log::error!("Handler type mismatch for '{}' on bus '{}'", event, bus);
+
+    EventBusError::HandlerTypeMismatch {
+        bus: bus.to_string(),
+        event: event.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AppRenderEvent, AppTickEvent, AppUpdateEvent, GamepadAxis, GamepadAxisMovedEvent,
+        GamepadButton, GamepadButtonPressedEvent, GamepadConnectedEvent, KeyCode, KeyEvent,
+        KeyPressedEvent, KeyReleasedEvent, KeyTypedEvent, MouseButton, MouseButtonPressedEvent,
+        MouseButtonReleasedEvent, MouseMovedEvent, MouseScrolledEvent, TextInputEvent,
+        WindowCloseEvent, WindowResizeEvent,
+    };
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    fn counter<T: Event>(bus: &str) -> Arc<AtomicUsize> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = count.clone();
+
+        subscribe_event(
+            bus,
+            move |_: &mut T| {
+                handler_count.fetch_add(1, Ordering::SeqCst);
+            },
+            0,
+        )
+        .unwrap();
+
+        count
+    }
+
+    #[test]
+    fn event_ids_are_unique_per_type() {
+        assert_eq!(
+            get_event_id::<KeyPressedEvent>(),
+            get_event_id::<KeyPressedEvent>()
+        );
+        assert_ne!(
+            get_event_id::<KeyPressedEvent>(),
+            get_event_id::<KeyReleasedEvent>()
+        );
+        assert_ne!(
+            get_event_id::<WindowResizeEvent>(),
+            get_event_id::<dyn Event>()
+        );
+    }
+
+    #[test]
+    fn routes_each_event_type_to_its_own_handlers() {
+        let _bus = EventBus::new("test_routes_each_event_type");
+        let bus = "test_routes_each_event_type";
+
+        let resize = counter::<WindowResizeEvent>(bus);
+        let close = counter::<WindowCloseEvent>(bus);
+        let tick = counter::<AppTickEvent>(bus);
+        let update = counter::<AppUpdateEvent>(bus);
+        let render = counter::<AppRenderEvent>(bus);
+        let key_pressed = counter::<KeyPressedEvent>(bus);
+        let key_released = counter::<KeyReleasedEvent>(bus);
+        let key_typed = counter::<KeyTypedEvent>(bus);
+        let text_input = counter::<TextInputEvent>(bus);
+        let mouse_moved = counter::<MouseMovedEvent>(bus);
+        let mouse_scrolled = counter::<MouseScrolledEvent>(bus);
+        let button_pressed = counter::<MouseButtonPressedEvent>(bus);
+        let button_released = counter::<MouseButtonReleasedEvent>(bus);
+        let gamepad_connected = counter::<GamepadConnectedEvent>(bus);
+        let gamepad_button = counter::<GamepadButtonPressedEvent>(bus);
+        let gamepad_axis = counter::<GamepadAxisMovedEvent>(bus);
+
+        dispatch_event(bus, &mut WindowResizeEvent::new(800, 600)).unwrap();
+        dispatch_event(bus, &mut WindowCloseEvent::new()).unwrap();
+        dispatch_event(bus, &mut AppTickEvent::new()).unwrap();
+        dispatch_event(bus, &mut AppUpdateEvent::new()).unwrap();
+        dispatch_event(bus, &mut AppRenderEvent::new()).unwrap();
+        dispatch_event(bus, &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+        dispatch_event(bus, &mut KeyPressedEvent::new(KeyCode::B, 0)).unwrap();
+        dispatch_event(bus, &mut KeyReleasedEvent::new(KeyCode::A)).unwrap();
+        dispatch_event(bus, &mut KeyTypedEvent::new('a')).unwrap();
+        dispatch_event(bus, &mut TextInputEvent::new("日本")).unwrap();
+        dispatch_event(bus, &mut MouseMovedEvent::new(1.0, 2.0)).unwrap();
+        dispatch_event(bus, &mut MouseScrolledEvent::new(0.0, 1.0)).unwrap();
+        dispatch_event(bus, &mut MouseButtonPressedEvent::new(MouseButton::Left)).unwrap();
+        dispatch_event(bus, &mut GamepadConnectedEvent::new(0, "Pad")).unwrap();
+        dispatch_event(
+            bus,
+            &mut GamepadButtonPressedEvent::new(0, GamepadButton::A),
+        )
+        .unwrap();
+        dispatch_event(
+            bus,
+            &mut GamepadAxisMovedEvent::new(0, GamepadAxis::LeftX, 0.5),
+        )
+        .unwrap();
+
+        assert_eq!(resize.load(Ordering::SeqCst), 1);
+        assert_eq!(close.load(Ordering::SeqCst), 1);
+        assert_eq!(tick.load(Ordering::SeqCst), 1);
+        assert_eq!(update.load(Ordering::SeqCst), 1);
+        assert_eq!(render.load(Ordering::SeqCst), 1);
+        assert_eq!(key_pressed.load(Ordering::SeqCst), 2);
+        assert_eq!(key_released.load(Ordering::SeqCst), 1);
+        assert_eq!(key_typed.load(Ordering::SeqCst), 1);
+        assert_eq!(text_input.load(Ordering::SeqCst), 1);
+        assert_eq!(mouse_moved.load(Ordering::SeqCst), 1);
+        assert_eq!(mouse_scrolled.load(Ordering::SeqCst), 1);
+        assert_eq!(button_pressed.load(Ordering::SeqCst), 1);
+        assert_eq!(button_released.load(Ordering::SeqCst), 0);
+        assert_eq!(gamepad_connected.load(Ordering::SeqCst), 1);
+        assert_eq!(gamepad_button.load(Ordering::SeqCst), 1);
+        assert_eq!(gamepad_axis.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handlers_receive_the_dispatched_event() {
+        let _bus = EventBus::new("test_handlers_receive_event");
+        let bus = "test_handlers_receive_event";
+        let width = Arc::new(AtomicUsize::new(0));
+        let handler_width = width.clone();
+
+        subscribe_event(
+            bus,
+            move |event: &mut WindowResizeEvent| {
+                handler_width.store(event.get_width() as usize, Ordering::SeqCst);
+            },
+            0,
+        )
+        .unwrap();
+
+        dispatch_event(bus, &mut WindowResizeEvent::new(1920, 1080)).unwrap();
+
+        assert_eq!(width.load(Ordering::SeqCst), 1920);
+    }
+
+    #[test]
+    fn handlers_run_in_priority_order() {
+        let _bus = EventBus::new("test_priority_order");
+        let bus = "test_priority_order";
+        let order = Arc::new(Mutex::new(vec![]));
+
+        for priority in &[1, 5, 3] {
+            let order = order.clone();
+            let priority = *priority;
+
+            subscribe_event(
+                bus,
+                move |_: &mut KeyPressedEvent| order.lock().unwrap().push(priority),
+                priority,
+            )
+            .unwrap();
+        }
+
+        dispatch_event(bus, &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(*order.lock().unwrap(), vec![5, 3, 1]);
+    }
+
+    #[test]
+    fn buses_are_isolated() {
+        let _first = EventBus::new("test_isolated_first");
+        let _second = EventBus::new("test_isolated_second");
+
+        let first = counter::<KeyPressedEvent>("test_isolated_first");
+        let second = counter::<KeyPressedEvent>("test_isolated_second");
+
+        dispatch_event(
+            "test_isolated_first",
+            &mut KeyPressedEvent::new(KeyCode::A, 0),
+        )
+        .unwrap();
+
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+        assert_eq!(second.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn invalid_bus_is_an_error() {
+        assert_eq!(
+            dispatch_event("test_missing_bus", &mut WindowCloseEvent::new()),
+            Err(EventBusError::InvalidBus("test_missing_bus".to_string()))
+        );
+        assert_eq!(
+            subscribe_event("test_missing_bus", |_: &mut WindowCloseEvent| {}, 0),
+            Err(EventBusError::InvalidBus("test_missing_bus".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_bus_is_logged() {
+        let logs = match nain_log::capture() {
+            Ok(logs) => logs,
+            // Another logger owns the records, so there is nothing to check.
+            Err(nain_log::LogError::AlreadyInitialized) => return,
+            Err(error) => panic!("{}", error),
+        };
+
+        let _ = dispatch_event("test_logged_missing_bus", &mut WindowCloseEvent::new());
+
+        assert!(logs.contains(
+            &nain_log::LogQuery::new()
+                .level(nain_log::Level::Warn)
+                .target("nain_events::event_bus")
+                .contains("Cannot dispatch event on invalid bus: 'test_logged_missing_bus'")
+        ));
+    }
+
+    #[test]
+    fn dropped_bus_is_removed() {
+        let bus = EventBus::new("test_dropped_bus");
+        drop(bus);
+
+        assert!(dispatch_event("test_dropped_bus", &mut WindowCloseEvent::new()).is_err());
+    }
+
+    #[test]
+    fn type_mismatch_is_an_error() {
+        let _bus = EventBus::new("test_type_mismatch");
+        let bus = "test_type_mismatch";
+
+        EVENT_HANDLER_MAP.read().unwrap()[bus]
+            .write()
+            .unwrap()
+            .insert(
+                get_event_id::<KeyPressedEvent>(),
+                Box::new(EventHandlers::<KeyReleasedEvent>::default()),
+            );
+
+        assert!(matches!(
+            dispatch_event(bus, &mut KeyPressedEvent::new(KeyCode::A, 0)),
+            Err(EventBusError::HandlerTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            subscribe_event(bus, |_: &mut KeyPressedEvent| {}, 0),
+            Err(EventBusError::HandlerTypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn unsubscribe_removes_only_that_handler() {
+        let bus = EventBus::new("test_unsubscribe");
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = count.clone();
+
+        let handle = subscribe_event(
+            bus.get_name(),
+            move |_: &mut KeyPressedEvent| {
+                handler_count.fetch_add(1, Ordering::SeqCst);
+            },
+            0,
+        )
+        .unwrap();
+        let other = counter::<KeyPressedEvent>(bus.get_name());
+
+        assert_eq!(bus.handler_count(), 2);
+        assert!(bus.unsubscribe(handle));
+        assert_eq!(bus.handler_count(), 1);
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(other.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn unsubscribe_twice_returns_false() {
+        let bus = EventBus::new("test_unsubscribe_twice");
+        let handle = subscribe_event(bus.get_name(), |_: &mut WindowCloseEvent| {}, 0).unwrap();
+
+        assert!(unsubscribe_event(&handle));
+        assert!(!unsubscribe_event(&handle));
+        assert!(!handle.unsubscribe());
+    }
+
+    #[test]
+    fn unsubscribe_through_other_bus_is_ignored() {
+        let bus = EventBus::new("test_unsubscribe_other_first");
+        let other = EventBus::new("test_unsubscribe_other_second");
+        let handle = subscribe_event(bus.get_name(), |_: &mut WindowCloseEvent| {}, 0).unwrap();
+
+        assert!(!other.unsubscribe(handle));
+        assert_eq!(bus.handler_count(), 1);
+    }
+
+    #[test]
+    fn guard_unsubscribes_on_drop() {
+        let bus = EventBus::new("test_guard_drop");
+        let guard = subscribe_event(bus.get_name(), |_: &mut MouseMovedEvent| {}, 0)
+            .unwrap()
+            .into_guard();
+
+        assert_eq!(bus.handler_count(), 1);
+        drop(guard);
+        assert_eq!(bus.handler_count(), 0);
+    }
+
+    #[test]
+    fn released_guard_keeps_handler() {
+        let bus = EventBus::new("test_guard_release");
+        let handle = subscribe_event(bus.get_name(), |_: &mut MouseMovedEvent| {}, 0)
+            .unwrap()
+            .into_guard()
+            .release();
+
+        assert_eq!(bus.handler_count(), 1);
+        assert!(handle.unsubscribe());
+    }
+
+    #[test]
+    fn guard_outliving_bus_is_harmless() {
+        let bus = EventBus::new("test_guard_outlives_bus");
+        let guard = subscribe_event(bus.get_name(), |_: &mut MouseMovedEvent| {}, 0)
+            .unwrap()
+            .into_guard();
+
+        drop(bus);
+        drop(guard);
+    }
+
+    #[test]
+    fn clear_bus_removes_every_handler() {
+        let bus = EventBus::new("test_clear_bus");
+        let key = counter::<KeyPressedEvent>(bus.get_name());
+        let handle = subscribe_event(bus.get_name(), |_: &mut WindowCloseEvent| {}, 0).unwrap();
+
+        assert_eq!(bus.handler_count(), 2);
+        bus.clear_bus();
+        assert_eq!(bus.handler_count(), 0);
+        assert!(!handle.unsubscribe());
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(key.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn dispatch_reports_unconsumed_events() {
+        let bus = EventBus::new("test_unconsumed");
+        counter::<KeyPressedEvent>(bus.get_name());
+
+        let mut event = KeyPressedEvent::new(KeyCode::A, 0);
+
+        assert_eq!(dispatch_event(bus.get_name(), &mut event), Ok(false));
+        assert!(!event.is_handled());
+    }
+
+    #[test]
+    fn stop_skips_lower_priority_handlers() {
+        let bus = EventBus::new("test_stop_propagation");
+        let game = counter::<MouseButtonPressedEvent>(bus.get_name());
+        let ui = Arc::new(AtomicUsize::new(0));
+        let ui_count = ui.clone();
+
+        subscribe_event(
+            bus.get_name(),
+            move |_: &mut MouseButtonPressedEvent| {
+                ui_count.fetch_add(1, Ordering::SeqCst);
+                Propagation::Stop
+            },
+            10,
+        )
+        .unwrap();
+
+        let mut event = MouseButtonPressedEvent::new(MouseButton::Left);
+
+        assert_eq!(dispatch_event(bus.get_name(), &mut event), Ok(true));
+        assert!(event.is_handled());
+        assert_eq!(ui.load(Ordering::SeqCst), 1);
+        assert_eq!(game.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn marking_handled_skips_lower_priority_handlers() {
+        let bus = EventBus::new("test_mark_handled");
+        let game = counter::<MouseButtonPressedEvent>(bus.get_name());
+
+        subscribe_event(
+            bus.get_name(),
+            |event: &mut MouseButtonPressedEvent| event.set_handled(true),
+            10,
+        )
+        .unwrap();
+
+        let consumed = dispatch_event(
+            bus.get_name(),
+            &mut MouseButtonPressedEvent::new(MouseButton::Left),
+        );
+
+        assert_eq!(consumed, Ok(true));
+        assert_eq!(game.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn continue_reaches_every_handler() {
+        let bus = EventBus::new("test_continue_propagation");
+        let first = counter::<KeyReleasedEvent>(bus.get_name());
+
+        subscribe_event(
+            bus.get_name(),
+            |_: &mut KeyReleasedEvent| Propagation::Continue,
+            10,
+        )
+        .unwrap();
+
+        let consumed = dispatch_event(bus.get_name(), &mut KeyReleasedEvent::new(KeyCode::A));
+
+        assert_eq!(consumed, Ok(false));
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handled_events_are_not_dispatched() {
+        let bus = EventBus::new("test_already_handled");
+        let count = counter::<KeyReleasedEvent>(bus.get_name());
+
+        let mut event = KeyReleasedEvent::new(KeyCode::A);
+        event.set_handled(true);
+
+        assert_eq!(dispatch_event(bus.get_name(), &mut event), Ok(true));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn events_report_every_category_they_belong_to() {
+        let key = KeyPressedEvent::new(KeyCode::A, 0);
+        let button = MouseButtonPressedEvent::new(MouseButton::Left);
+        let resize = WindowResizeEvent::new(1, 1);
+
+        assert!(key.is_in_category(EventCategory::INPUT));
+        assert!(key.is_in_category(EventCategory::KEYBOARD));
+        assert!(!key.is_in_category(EventCategory::MOUSE));
+        assert!(button.is_in_category(EventCategory::MOUSE_BUTTON));
+        assert!(button.is_in_category(EventCategory::MOUSE));
+        assert!(!button.is_in_category(EventCategory::KEYBOARD));
+        assert_eq!(resize.get_category_flags(), EventCategory::APPLICATION);
+        assert!(!resize.is_in_category(EventCategory::NONE));
+    }
+
+    #[test]
+    fn category_handlers_only_see_matching_events() {
+        let bus = EventBus::new("test_category_filter");
+        let names = Arc::new(Mutex::new(vec![]));
+        let handler_names = names.clone();
+
+        subscribe_category(
+            bus.get_name(),
+            EventCategory::KEYBOARD | EventCategory::MOUSE_BUTTON,
+            move |event: &mut dyn Event| handler_names.lock().unwrap().push(event.get_name()),
+            0,
+        )
+        .unwrap();
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+        dispatch_event(bus.get_name(), &mut MouseMovedEvent::new(0.0, 0.0)).unwrap();
+        dispatch_event(
+            bus.get_name(),
+            &mut MouseButtonReleasedEvent::new(MouseButton::Left),
+        )
+        .unwrap();
+        dispatch_event(bus.get_name(), &mut WindowCloseEvent::new()).unwrap();
+
+        assert_eq!(
+            *names.lock().unwrap(),
+            vec![
+                "EventType::KeyPressed".to_string(),
+                "EventType::MouseButtonReleased".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn category_handlers_run_in_priority_order_with_typed_handlers() {
+        let bus = EventBus::new("test_category_priority");
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let typed_order = order.clone();
+        subscribe_event(
+            bus.get_name(),
+            move |_: &mut KeyPressedEvent| typed_order.lock().unwrap().push("typed"),
+            5,
+        )
+        .unwrap();
+
+        let high_order = order.clone();
+        subscribe_category(
+            bus.get_name(),
+            EventCategory::INPUT,
+            move |_: &mut dyn Event| high_order.lock().unwrap().push("high"),
+            10,
+        )
+        .unwrap();
+
+        let low_order = order.clone();
+        subscribe_category(
+            bus.get_name(),
+            EventCategory::INPUT,
+            move |_: &mut dyn Event| low_order.lock().unwrap().push("low"),
+            1,
+        )
+        .unwrap();
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(*order.lock().unwrap(), vec!["high", "typed", "low"]);
+    }
+
+    #[test]
+    fn category_handlers_can_stop_propagation() {
+        let bus = EventBus::new("test_category_stop");
+        let game = counter::<MouseButtonPressedEvent>(bus.get_name());
+
+        subscribe_category(
+            bus.get_name(),
+            EventCategory::MOUSE,
+            |_: &mut dyn Event| Propagation::Stop,
+            10,
+        )
+        .unwrap();
+
+        let consumed = dispatch_event(
+            bus.get_name(),
+            &mut MouseButtonPressedEvent::new(MouseButton::Left),
+        );
+
+        assert_eq!(consumed, Ok(true));
+        assert_eq!(game.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn category_handlers_receive_dyn_events() {
+        let bus = EventBus::new("test_category_dyn");
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = count.clone();
+
+        subscribe_category(
+            bus.get_name(),
+            EventCategory::APPLICATION,
+            move |_: &mut dyn Event| {
+                handler_count.fetch_add(1, Ordering::SeqCst);
+            },
+            0,
+        )
+        .unwrap();
+
+        let event: &mut dyn Event = &mut WindowResizeEvent::new(1, 1);
+        dispatch_event(bus.get_name(), event).unwrap();
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handlers_can_subscribe_during_dispatch() {
+        let bus = EventBus::new("test_reentrant_subscribe");
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = count.clone();
+
+        subscribe_event(
+            bus.get_name(),
+            move |_: &mut KeyPressedEvent| {
+                let count = handler_count.clone();
+                subscribe_event(
+                    "test_reentrant_subscribe",
+                    move |_: &mut KeyPressedEvent| {
+                        count.fetch_add(1, Ordering::SeqCst);
+                    },
+                    0,
+                )
+                .unwrap();
+            },
+            10,
+        )
+        .unwrap();
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(bus.handler_count(), 2);
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handlers_can_unsubscribe_during_dispatch() {
+        let bus = EventBus::new("test_reentrant_unsubscribe");
+        let handle = Arc::new(Mutex::new(None));
+        let handler_handle = handle.clone();
+
+        *handle.lock().unwrap() = Some(
+            subscribe_event(
+                bus.get_name(),
+                move |_: &mut KeyPressedEvent| {
+                    if let Some(handle) = handler_handle.lock().unwrap().take() {
+                        unsubscribe_event(&handle);
+                    }
+                },
+                0,
+            )
+            .unwrap(),
+        );
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(bus.handler_count(), 0);
+    }
+
+    #[test]
+    fn handlers_can_dispatch_during_dispatch() {
+        let bus = EventBus::new("test_reentrant_dispatch");
+        let released = counter::<KeyReleasedEvent>(bus.get_name());
+
+        subscribe_event(
+            bus.get_name(),
+            |event: &mut KeyPressedEvent| {
+                dispatch_event(
+                    "test_reentrant_dispatch",
+                    &mut KeyReleasedEvent::new(event.get_key_code()),
+                )
+                .unwrap();
+            },
+            0,
+        )
+        .unwrap();
+
+        dispatch_event(bus.get_name(), &mut KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(released.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn queued_events_wait_for_flush() {
+        let bus = EventBus::new("test_queue_wait");
+        let count = counter::<WindowResizeEvent>(bus.get_name());
+
+        bus.enqueue(WindowResizeEvent::new(1, 1)).unwrap();
+        bus.enqueue(WindowResizeEvent::new(2, 2)).unwrap();
+
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(bus.pending_count(), 2);
+        assert_eq!(bus.flush(), Ok(2));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert_eq!(bus.pending_count(), 0);
+        assert_eq!(bus.flush(), Ok(0));
+    }
+
+    #[test]
+    fn queued_events_keep_enqueue_order_across_types() {
+        let bus = EventBus::new("test_queue_order");
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let key_order = order.clone();
+        subscribe_event(
+            bus.get_name(),
+            move |event: &mut KeyPressedEvent| key_order.lock().unwrap().push(event.to_string()),
+            0,
+        )
+        .unwrap();
+
+        let close_order = order.clone();
+        subscribe_event(
+            bus.get_name(),
+            move |event: &mut WindowCloseEvent| close_order.lock().unwrap().push(event.to_string()),
+            0,
+        )
+        .unwrap();
+
+        bus.enqueue(KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+        bus.enqueue(WindowCloseEvent::new()).unwrap();
+        bus.enqueue(KeyPressedEvent::new(KeyCode::B, 0)).unwrap();
+        bus.flush().unwrap();
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                "KeyPressedEvent: A (0 repeats)",
+                "WindowCloseEvent",
+                "KeyPressedEvent: B (0 repeats)"
+            ]
+        );
+    }
+
+    #[test]
+    fn events_enqueued_while_flushing_wait_for_next_flush() {
+        let bus = EventBus::new("test_queue_reentrant");
+        let released = counter::<KeyReleasedEvent>(bus.get_name());
+
+        subscribe_event(
+            bus.get_name(),
+            |event: &mut KeyPressedEvent| {
+                enqueue_event(
+                    "test_queue_reentrant",
+                    KeyReleasedEvent::new(event.get_key_code()),
+                )
+                .unwrap();
+            },
+            0,
+        )
+        .unwrap();
+
+        bus.enqueue(KeyPressedEvent::new(KeyCode::A, 0)).unwrap();
+
+        assert_eq!(bus.flush(), Ok(1));
+        assert_eq!(released.load(Ordering::SeqCst), 0);
+        assert_eq!(bus.flush(), Ok(1));
+        assert_eq!(released.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn flush_until_leaves_later_events_queued() {
+        let bus = EventBus::new("test_queue_deadline");
+        let count = counter::<AppTickEvent>(bus.get_name());
+
+        bus.enqueue(AppTickEvent::new()).unwrap();
+        let deadline = Instant::now();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        bus.enqueue(AppTickEvent::new()).unwrap();
+
+        assert_eq!(bus.flush_until(deadline), Ok(1));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(bus.pending_count(), 1);
+    }
+
+    #[test]
+    fn events_can_be_enqueued_from_other_threads() {
+        let bus = EventBus::new("test_queue_threads");
+        let count = counter::<MouseMovedEvent>(bus.get_name());
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for i in 0..25 {
+                        enqueue_event("test_queue_threads", MouseMovedEvent::new(i as f64, 0.0))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(bus.flush(), Ok(100));
+        assert_eq!(count.load(Ordering::SeqCst), 100);
+    }
+
+    #[test]
+    fn queue_on_invalid_bus_is_an_error() {
+        assert!(enqueue_event("test_queue_missing", AppTickEvent::new()).is_err());
+        assert!(flush_events("test_queue_missing").is_err());
+    }
+
+    #[test]
+    fn dyn_events_reach_handlers_for_their_concrete_type() {
+        let bus = EventBus::new("test_dyn_dispatch");
+        let width = Arc::new(AtomicUsize::new(0));
+        let handler_width = width.clone();
+        let close = counter::<WindowCloseEvent>(bus.get_name());
+
+        subscribe_event(
+            bus.get_name(),
+            move |event: &mut WindowResizeEvent| {
+                handler_width.store(event.get_width() as usize, Ordering::SeqCst);
+                Propagation::Stop
+            },
+            0,
+        )
+        .unwrap();
+
+        let mut event: Box<dyn Event> = Box::new(WindowResizeEvent::new(640, 480));
+
+        assert_eq!(dispatch_event(bus.get_name(), event.as_mut()), Ok(true));
+        assert!(event.is_handled());
+        assert_eq!(width.load(Ordering::SeqCst), 640);
+        assert_eq!(close.load(Ordering::SeqCst), 0);
+    }
+}
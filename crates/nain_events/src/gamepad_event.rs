@@ -0,0 +1,228 @@
+use crate::event::{
+    event_category_flags, event_handled, event_type, Event, EventCategory, EventType,
+};
+use crate::input_code::{GamepadAxis, GamepadButton};
+use std::fmt;
+
+/// Shared accessor of every gamepad event. Gamepads are numbered from 0 in
+/// the order the platform reports them, and keep their number while
+/// connected.
+pub trait GamepadEvent: Event {
+    fn get_gamepad(&self) -> u32;
+}
+
+// Gamepad connected event
+
+pub struct GamepadConnectedEvent {
+    gamepad: u32,
+    name: String,
+    handled: bool,
+}
+
+impl Event for GamepadConnectedEvent {
+    event_category_flags!(INPUT | GAMEPAD);
+    event_type!(EventType::GamepadConnected);
+    event_handled!();
+}
+
+impl GamepadEvent for GamepadConnectedEvent {
+    fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+}
+
+impl fmt::Display for GamepadConnectedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "GamepadConnectedEvent: {} ({})",
+            self.gamepad, self.name
+        )
+    }
+}
+
+impl GamepadConnectedEvent {
+    pub fn new<T: Into<String>>(gamepad: u32, name: T) -> Self {
+        Self {
+            gamepad,
+            name: name.into(),
+            handled: false,
+        }
+    }
+
+    pub fn get_gamepad_name(&self) -> &str {
+        &self.name
+    }
+}
+
+// Gamepad disconnected event
+
+pub struct GamepadDisconnectedEvent {
+    gamepad: u32,
+    handled: bool,
+}
+
+impl Event for GamepadDisconnectedEvent {
+    event_category_flags!(INPUT | GAMEPAD);
+    event_type!(EventType::GamepadDisconnected);
+    event_handled!();
+}
+
+impl GamepadEvent for GamepadDisconnectedEvent {
+    fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+}
+
+impl fmt::Display for GamepadDisconnectedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "GamepadDisconnectedEvent: {}", self.gamepad)
+    }
+}
+
+impl GamepadDisconnectedEvent {
+    pub fn new(gamepad: u32) -> Self {
+        Self {
+            gamepad,
+            handled: false,
+        }
+    }
+}
+
+// Gamepad button pressed event
+
+pub struct GamepadButtonPressedEvent {
+    gamepad: u32,
+    button: GamepadButton,
+    handled: bool,
+}
+
+impl Event for GamepadButtonPressedEvent {
+    event_category_flags!(INPUT | GAMEPAD);
+    event_type!(EventType::GamepadButtonPressed);
+    event_handled!();
+}
+
+impl GamepadEvent for GamepadButtonPressedEvent {
+    fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+}
+
+impl fmt::Display for GamepadButtonPressedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "GamepadButtonPressedEvent: {} - {}",
+            self.gamepad, self.button
+        )
+    }
+}
+
+impl GamepadButtonPressedEvent {
+    pub fn new(gamepad: u32, button: GamepadButton) -> Self {
+        Self {
+            gamepad,
+            button,
+            handled: false,
+        }
+    }
+
+    pub fn get_button(&self) -> GamepadButton {
+        self.button
+    }
+}
+
+// Gamepad button released event
+
+pub struct GamepadButtonReleasedEvent {
+    gamepad: u32,
+    button: GamepadButton,
+    handled: bool,
+}
+
+impl Event for GamepadButtonReleasedEvent {
+    event_category_flags!(INPUT | GAMEPAD);
+    event_type!(EventType::GamepadButtonReleased);
+    event_handled!();
+}
+
+impl GamepadEvent for GamepadButtonReleasedEvent {
+    fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+}
+
+impl fmt::Display for GamepadButtonReleasedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "GamepadButtonReleasedEvent: {} - {}",
+            self.gamepad, self.button
+        )
+    }
+}
+
+impl GamepadButtonReleasedEvent {
+    pub fn new(gamepad: u32, button: GamepadButton) -> Self {
+        Self {
+            gamepad,
+            button,
+            handled: false,
+        }
+    }
+
+    pub fn get_button(&self) -> GamepadButton {
+        self.button
+    }
+}
+
+// Gamepad axis moved event
+
+pub struct GamepadAxisMovedEvent {
+    gamepad: u32,
+    axis: GamepadAxis,
+    value: f32,
+    handled: bool,
+}
+
+impl Event for GamepadAxisMovedEvent {
+    event_category_flags!(INPUT | GAMEPAD);
+    event_type!(EventType::GamepadAxisMoved);
+    event_handled!();
+}
+
+impl GamepadEvent for GamepadAxisMovedEvent {
+    fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+}
+
+impl fmt::Display for GamepadAxisMovedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "GamepadAxisMovedEvent: {} - {}: {}",
+            self.gamepad, self.axis, self.value
+        )
+    }
+}
+
+impl GamepadAxisMovedEvent {
+    pub fn new(gamepad: u32, axis: GamepadAxis, value: f32) -> Self {
+        Self {
+            gamepad,
+            axis,
+            value,
+            handled: false,
+        }
+    }
+
+    pub fn get_axis(&self) -> GamepadAxis {
+        self.axis
+    }
+
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! input_codes {
+    ($(#[$meta:meta])* $type:ident ($description:literal) { $($name:ident,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $type {
+            $($name,)*
+        }
+
+        impl $type {
+            pub const ALL: &'static [$type] = &[$($type::$name,)*];
+
+            pub fn get_name(&self) -> &'static str {
+                match self {
+                    $($type::$name => stringify!($name),)*
+                }
+            }
+        }
+
+        impl fmt::Display for $type {
+            fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+                write!(formater, "{}", self.get_name())
+            }
+        }
+
+        impl FromStr for $type {
+            type Err = String;
+
+            /// Parses the name returned by `get_name`, ignoring case.
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                $type::ALL
+                    .iter()
+                    .find(|code| code.get_name().eq_ignore_ascii_case(name))
+                    .copied()
+                    .ok_or_else(|| format!(concat!("Unknown ", $description, ": '{}'"), name))
+            }
+        }
+    };
+}
+
+input_codes! {
+    /// Platform independent key, named after its position on a US layout.
+    KeyCode ("key") {
+        Space,
+        Apostrophe,
+        Comma,
+        Minus,
+        Period,
+        Slash,
+        Num0,
+        Num1,
+        Num2,
+        Num3,
+        Num4,
+        Num5,
+        Num6,
+        Num7,
+        Num8,
+        Num9,
+        Semicolon,
+        Equal,
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        LeftBracket,
+        Backslash,
+        RightBracket,
+        GraveAccent,
+        World1,
+        World2,
+        Escape,
+        Enter,
+        Tab,
+        Backspace,
+        Insert,
+        Delete,
+        Right,
+        Left,
+        Down,
+        Up,
+        PageUp,
+        PageDown,
+        Home,
+        End,
+        CapsLock,
+        ScrollLock,
+        NumLock,
+        PrintScreen,
+        Pause,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        F25,
+        Kp0,
+        Kp1,
+        Kp2,
+        Kp3,
+        Kp4,
+        Kp5,
+        Kp6,
+        Kp7,
+        Kp8,
+        Kp9,
+        KpDecimal,
+        KpDivide,
+        KpMultiply,
+        KpSubtract,
+        KpAdd,
+        KpEnter,
+        KpEqual,
+        LeftShift,
+        LeftControl,
+        LeftAlt,
+        LeftSuper,
+        RightShift,
+        RightControl,
+        RightAlt,
+        RightSuper,
+        Menu,
+        Unknown,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    /// Any further button, numbered from 0 like the platform does.
+    Other(u8),
+}
+
+impl fmt::Display for MouseButton {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MouseButton::Other(index) => write!(formater, "Button{}", index),
+            button => write!(formater, "{:?}", button),
+        }
+    }
+}
+
+impl FromStr for MouseButton {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let button = match name.to_ascii_lowercase().as_str() {
+            "left" => MouseButton::Left,
+            "right" => MouseButton::Right,
+            "middle" => MouseButton::Middle,
+            "back" => MouseButton::Back,
+            "forward" => MouseButton::Forward,
+            other => other
+                .strip_prefix("button")
+                .and_then(|index| index.parse().ok())
+                .map(MouseButton::Other)
+                .ok_or_else(|| format!("Unknown mouse button: '{}'", name))?,
+        };
+
+        Ok(button)
+    }
+}
+
+input_codes! {
+    /// Gamepad button in the standard layout, named after the Xbox controller.
+    GamepadButton ("gamepad button") {
+        A,
+        B,
+        X,
+        Y,
+        LeftBumper,
+        RightBumper,
+        Back,
+        Start,
+        Guide,
+        LeftThumb,
+        RightThumb,
+        DpadUp,
+        DpadRight,
+        DpadDown,
+        DpadLeft,
+    }
+}
+
+input_codes! {
+    /// Gamepad axis in the standard layout. Sticks go from `-1.0` to `1.0`,
+    /// with positive Y pointing down, and triggers from `-1.0` at rest to
+    /// `1.0`.
+    GamepadAxis ("gamepad axis") {
+        LeftX,
+        LeftY,
+        RightX,
+        RightY,
+        LeftTrigger,
+        RightTrigger,
+    }
+}
+
+impl GamepadAxis {
+    /// Value of the axis when left alone.
+    pub fn get_rest_value(&self) -> f32 {
+        match self {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => -1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+bitflags! {
+    /// Modifier keys held while a key or button event happened.
+    pub struct Modifiers: u32 {
+        const SHIFT = 1 << 0;
+        const CONTROL = 1 << 1;
+        const ALT = 1 << 2;
+        const SUPER = 1 << 3;
+        const CAPS_LOCK = 1 << 4;
+        const NUM_LOCK = 1 << 5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_code_names() {
+        assert_eq!("a".parse(), Ok(KeyCode::A));
+        assert_eq!("LeftShift".parse(), Ok(KeyCode::LeftShift));
+        assert_eq!(KeyCode::F12.to_string(), "F12");
+        assert!("Hyper".parse::<KeyCode>().is_err());
+
+        for key_code in KeyCode::ALL {
+            assert_eq!(key_code.get_name().parse(), Ok(*key_code));
+        }
+    }
+
+    #[test]
+    fn parses_mouse_button_names() {
+        for button in &[
+            MouseButton::Left,
+            MouseButton::Middle,
+            MouseButton::Forward,
+            MouseButton::Other(6),
+        ] {
+            assert_eq!(button.to_string().parse(), Ok(*button));
+        }
+
+        assert!("button".parse::<MouseButton>().is_err());
+    }
+
+    #[test]
+    fn parses_gamepad_names() {
+        assert_eq!("dpadup".parse(), Ok(GamepadButton::DpadUp));
+        assert_eq!("RightTrigger".parse(), Ok(GamepadAxis::RightTrigger));
+        assert_eq!(GamepadAxis::LeftTrigger.get_rest_value(), -1.0);
+        assert_eq!(GamepadAxis::RightY.get_rest_value(), 0.0);
+        assert_eq!(
+            "Paddle".parse::<GamepadButton>(),
+            Err("Unknown gamepad button: 'Paddle'".to_string())
+        );
+    }
+}
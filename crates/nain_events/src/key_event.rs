@@ -1,26 +1,46 @@
-use crate::event::{event_category_flags, event_type, Event, EventCategory, EventType};
+use crate::event::{
+    event_category_flags, event_handled, event_type, Event, EventCategory, EventType,
+};
+use crate::input_code::{KeyCode, Modifiers};
 use std::fmt;
 
-trait KeyEvent: Event {
-    fn get_key_code(&self) -> u32;
+/// Shared accessors of the key pressed and released events.
+pub trait KeyEvent: Event {
+    fn get_key_code(&self) -> KeyCode;
+    /// Platform specific code of the physical key, for keys without a
+    /// `KeyCode` or for remembering a binding across layouts.
+    fn get_scancode(&self) -> u32;
+    fn get_modifiers(&self) -> Modifiers;
 }
 
 // Key pressed event
 
 pub struct KeyPressedEvent {
-    key_code: u32,
+    key_code: KeyCode,
+    scancode: u32,
+    modifiers: Modifiers,
     repeat_count: u32,
+    handled: bool,
 }
 
 impl Event for KeyPressedEvent {
-    event_category_flags!(EventCategory::Keyboard);
+    event_category_flags!(INPUT | KEYBOARD);
     event_type!(EventType::KeyPressed);
+    event_handled!();
 }
 
 impl KeyEvent for KeyPressedEvent {
-    fn get_key_code(&self) -> u32 {
+    fn get_key_code(&self) -> KeyCode {
         self.key_code
     }
+
+    fn get_scancode(&self) -> u32 {
+        self.scancode
+    }
+
+    fn get_modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
 }
 
 impl fmt::Display for KeyPressedEvent {
@@ -34,13 +54,26 @@ impl fmt::Display for KeyPressedEvent {
 }
 
 impl KeyPressedEvent {
-    pub fn new(key_code: u32, repeat_count: u32) -> Self {
+    pub fn new(key_code: KeyCode, repeat_count: u32) -> Self {
         Self {
             key_code,
+            scancode: 0,
+            modifiers: Modifiers::empty(),
             repeat_count,
+            handled: false,
         }
     }
 
+    pub fn scancode(mut self, scancode: u32) -> Self {
+        self.scancode = scancode;
+        self
+    }
+
+    pub fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
     pub fn get_repeat_count(&self) -> u32 {
         self.repeat_count
     }
@@ -49,28 +82,125 @@ impl KeyPressedEvent {
 // Key released event
 
 pub struct KeyReleasedEvent {
-    key_code: u32,
+    key_code: KeyCode,
+    scancode: u32,
+    modifiers: Modifiers,
+    handled: bool,
 }
 
 impl Event for KeyReleasedEvent {
-    event_category_flags!(EventCategory::Keyboard);
+    event_category_flags!(INPUT | KEYBOARD);
     event_type!(EventType::KeyReleased);
+    event_handled!();
 }
 
 impl KeyEvent for KeyReleasedEvent {
-    fn get_key_code(&self) -> u32 {
+    fn get_key_code(&self) -> KeyCode {
         self.key_code
     }
+
+    fn get_scancode(&self) -> u32 {
+        self.scancode
+    }
+
+    fn get_modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
 }
 
 impl fmt::Display for KeyReleasedEvent {
     fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
-        write!(formater, "KeyPressedEvent: {}", self.key_code)
+        write!(formater, "KeyReleasedEvent: {}", self.key_code)
     }
 }
 
 impl KeyReleasedEvent {
-    pub fn new(key_code: u32) -> Self {
-        Self { key_code }
+    pub fn new(key_code: KeyCode) -> Self {
+        Self {
+            key_code,
+            scancode: 0,
+            modifiers: Modifiers::empty(),
+            handled: false,
+        }
+    }
+
+    pub fn scancode(mut self, scancode: u32) -> Self {
+        self.scancode = scancode;
+        self
+    }
+
+    pub fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+}
+
+// Key typed event
+
+/// A character produced by the keyboard, after layout and modifiers are
+/// applied. Use this rather than key presses for text entry.
+pub struct KeyTypedEvent {
+    character: char,
+    handled: bool,
+}
+
+impl Event for KeyTypedEvent {
+    event_category_flags!(INPUT | KEYBOARD);
+    event_type!(EventType::KeyTyped);
+    event_handled!();
+}
+
+impl fmt::Display for KeyTypedEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "KeyTypedEvent: {:?}", self.character)
+    }
+}
+
+impl KeyTypedEvent {
+    pub fn new(character: char) -> Self {
+        Self {
+            character,
+            handled: false,
+        }
+    }
+
+    pub fn get_char(&self) -> char {
+        self.character
+    }
+}
+
+// Text input event
+
+/// Text committed at once, sent after the `KeyTypedEvent`s of its
+/// characters. GLFW offers no composition, so its windows send the
+/// characters typed since the previous update. Text boxes should listen to
+/// one of the two events, not both.
+pub struct TextInputEvent {
+    text: String,
+    handled: bool,
+}
+
+impl Event for TextInputEvent {
+    event_category_flags!(INPUT | KEYBOARD);
+    event_type!(EventType::TextInput);
+    event_handled!();
+}
+
+impl fmt::Display for TextInputEvent {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "TextInputEvent: {:?}", self.text)
+    }
+}
+
+impl TextInputEvent {
+    pub fn new<T: Into<String>>(text: T) -> Self {
+        Self {
+            text: text.into(),
+            handled: false,
+        }
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
     }
 }
@@ -1,18 +1,37 @@
 pub use self::{
-    application_event::{WindowCloseEvent, WindowResizeEvent},
-    event::{Event, EventCategory, EventType},
-    event_bus::{EventBus, dispatch_event, subscribe_event},
-    key_event::{KeyPressedEvent, KeyReleasedEvent},
+    action_event::ActionEvent,
+    application_event::{
+        AppRenderEvent, AppTickEvent, AppUpdateEvent, WindowCloseEvent, WindowFocusEvent,
+        WindowLostFocusEvent, WindowMovedEvent, WindowResizeEvent,
+    },
+    event::{AsEvent, Event, EventCategory, EventType, Propagation},
+    event_bus::{
+        dispatch_event, enqueue_event, flush_events, flush_events_until, subscribe_category,
+        subscribe_event, unsubscribe_event, EventBus, EventBusError, SubscriptionGuard,
+        SubscriptionHandle,
+    },
+    gamepad_event::{
+        GamepadAxisMovedEvent, GamepadButtonPressedEvent, GamepadButtonReleasedEvent,
+        GamepadConnectedEvent, GamepadDisconnectedEvent, GamepadEvent,
+    },
+    input_code::{GamepadAxis, GamepadButton, KeyCode, Modifiers, MouseButton},
+    key_event::{KeyEvent, KeyPressedEvent, KeyReleasedEvent, KeyTypedEvent, TextInputEvent},
     mouse_event::{
-        MouseButtonPressedEvent, MouseButtonReleasedEvent, MouseMovedEvent, MouseScrolledEvent,
+        MouseButtonEvent, MouseButtonPressedEvent, MouseButtonReleasedEvent, MouseMovedEvent,
+        MouseScrolledEvent,
     },
 };
 
+mod action_event;
 mod application_event;
 mod event;
 mod event_bus;
+mod gamepad_event;
+mod input_code;
 mod key_event;
 mod mouse_event;
 
+#[macro_use]
+extern crate bitflags;
 #[macro_use]
 extern crate lazy_static;
@@ -1,4 +1,7 @@
-use crate::event::{event_category_flags, event_type, Event, EventCategory, EventType};
+use crate::event::{
+    event_category_flags, event_handled, event_type, Event, EventCategory, EventType,
+};
+use crate::input_code::{Modifiers, MouseButton};
 use std::fmt;
 
 // Mouse moved event
@@ -6,11 +9,15 @@ use std::fmt;
 pub struct MouseMovedEvent {
     x_pos: f64,
     y_pos: f64,
+    x_delta: f64,
+    y_delta: f64,
+    handled: bool,
 }
 
 impl Event for MouseMovedEvent {
-    event_category_flags!(EventCategory::Mouse);
+    event_category_flags!(INPUT | MOUSE);
     event_type!(EventType::MouseMoved);
+    event_handled!();
 }
 
 impl fmt::Display for MouseMovedEvent {
@@ -25,7 +32,19 @@ impl fmt::Display for MouseMovedEvent {
 
 impl MouseMovedEvent {
     pub fn new(x_pos: f64, y_pos: f64) -> Self {
-        Self { x_pos, y_pos }
+        Self::with_delta(x_pos, y_pos, 0.0, 0.0)
+    }
+
+    /// Creates the event with the motion since the previous one, which is
+    /// what matters while the cursor is captured.
+    pub fn with_delta(x_pos: f64, y_pos: f64, x_delta: f64, y_delta: f64) -> Self {
+        Self {
+            x_pos,
+            y_pos,
+            x_delta,
+            y_delta,
+            handled: false,
+        }
     }
 
     pub fn get_x(&self) -> f64 {
@@ -35,6 +54,14 @@ impl MouseMovedEvent {
     pub fn get_y(&self) -> f64 {
         self.y_pos
     }
+
+    pub fn get_x_delta(&self) -> f64 {
+        self.x_delta
+    }
+
+    pub fn get_y_delta(&self) -> f64 {
+        self.y_delta
+    }
 }
 
 // Mouse scrolled event
@@ -42,11 +69,13 @@ impl MouseMovedEvent {
 pub struct MouseScrolledEvent {
     mouse_x_offset: f64,
     mouse_y_offset: f64,
+    handled: bool,
 }
 
 impl Event for MouseScrolledEvent {
-    event_category_flags!(EventCategory::Mouse);
+    event_category_flags!(INPUT | MOUSE);
     event_type!(EventType::MouseScrolled);
+    event_handled!();
 }
 
 impl fmt::Display for MouseScrolledEvent {
@@ -65,6 +94,7 @@ impl MouseScrolledEvent {
         Self {
             mouse_x_offset,
             mouse_y_offset,
+            handled: false,
         }
     }
 
@@ -79,25 +109,34 @@ impl MouseScrolledEvent {
 
 // Mouse button events
 
-trait MouseButton: Event {
-    fn get_mouse_button(&self) -> u32;
+/// Shared accessors of the mouse button pressed and released events.
+pub trait MouseButtonEvent: Event {
+    fn get_mouse_button(&self) -> MouseButton;
+    fn get_modifiers(&self) -> Modifiers;
 }
 
 // Mouse button pressed event
 
 pub struct MouseButtonPressedEvent {
-    button: u32,
+    button: MouseButton,
+    modifiers: Modifiers,
+    handled: bool,
 }
 
 impl Event for MouseButtonPressedEvent {
-    event_category_flags!(EventCategory::Keyboard);
+    event_category_flags!(INPUT | MOUSE | MOUSE_BUTTON);
     event_type!(EventType::MouseButtonPressed);
+    event_handled!();
 }
 
-impl MouseButton for MouseButtonPressedEvent {
-    fn get_mouse_button(&self) -> u32 {
+impl MouseButtonEvent for MouseButtonPressedEvent {
+    fn get_mouse_button(&self) -> MouseButton {
         self.button
     }
+
+    fn get_modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
 }
 
 impl fmt::Display for MouseButtonPressedEvent {
@@ -107,26 +146,42 @@ impl fmt::Display for MouseButtonPressedEvent {
 }
 
 impl MouseButtonPressedEvent {
-    pub fn new(button: u32) -> Self {
-        Self { button }
+    pub fn new(button: MouseButton) -> Self {
+        Self {
+            button,
+            modifiers: Modifiers::empty(),
+            handled: false,
+        }
+    }
+
+    pub fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
     }
 }
 
 // Mouse button released event
 
 pub struct MouseButtonReleasedEvent {
-    button: u32,
+    button: MouseButton,
+    modifiers: Modifiers,
+    handled: bool,
 }
 
 impl Event for MouseButtonReleasedEvent {
-    event_category_flags!(EventCategory::Keyboard);
+    event_category_flags!(INPUT | MOUSE | MOUSE_BUTTON);
     event_type!(EventType::MouseButtonReleased);
+    event_handled!();
 }
 
-impl MouseButton for MouseButtonReleasedEvent {
-    fn get_mouse_button(&self) -> u32 {
+impl MouseButtonEvent for MouseButtonReleasedEvent {
+    fn get_mouse_button(&self) -> MouseButton {
         self.button
     }
+
+    fn get_modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
 }
 
 impl fmt::Display for MouseButtonReleasedEvent {
@@ -136,7 +191,16 @@ impl fmt::Display for MouseButtonReleasedEvent {
 }
 
 impl MouseButtonReleasedEvent {
-    pub fn new(button: u32) -> Self {
-        Self { button }
+    pub fn new(button: MouseButton) -> Self {
+        Self {
+            button,
+            modifiers: Modifiers::empty(),
+            handled: false,
+        }
+    }
+
+    pub fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
     }
 }
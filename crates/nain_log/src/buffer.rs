@@ -0,0 +1,263 @@
+use crate::config::is_within;
+use crate::origin::{Origin, CLIENT_TARGET, CORE_TARGET};
+use chrono::{DateTime, Local};
+use log::Level;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A record kept by a `LogBuffer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    level: Level,
+    target: String,
+    timestamp: DateTime<Local>,
+    message: String,
+}
+
+impl LogRecord {
+    pub fn new<T: Into<String>, M: Into<String>>(level: Level, target: T, message: M) -> Self {
+        Self {
+            level,
+            target: target.into(),
+            timestamp: Local::now(),
+            message: message.into(),
+        }
+    }
+
+    pub fn get_level(&self) -> Level {
+        self.level
+    }
+
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn get_timestamp(&self) -> DateTime<Local> {
+        self.timestamp
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "[{}] [{}] {}",
+            self.target, self.level, self.message
+        )
+    }
+}
+
+/// Selects records from a `LogBuffer`. Every condition set has to match.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    level: Option<Level>,
+    target: Option<String>,
+    text: Option<String>,
+    since: Option<DateTime<Local>>,
+    last: Option<usize>,
+}
+
+impl LogQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records at least as severe as `level`.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Records from `target` and the modules inside it. `NAIN` and `APP`
+    /// select a whole side, see `Origin`.
+    pub fn target<T: Into<String>>(mut self, target: T) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Records whose message contains `text`.
+    pub fn contains<T: Into<String>>(mut self, text: T) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn since(mut self, timestamp: DateTime<Local>) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    /// Only the `count` most recent of the matching records.
+    pub fn last(mut self, count: usize) -> Self {
+        self.last = Some(count);
+        self
+    }
+
+    fn matches(&self, record: &LogRecord) -> bool {
+        let target_matches = self.target.as_deref().is_none_or(|target| match target {
+            CORE_TARGET | CLIENT_TARGET => Origin::of(&record.target).get_target() == target,
+            target => is_within(&record.target, target),
+        });
+
+        target_matches
+            && self.level.is_none_or(|level| record.level <= level)
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|text| record.message.contains(text))
+            && self.since.is_none_or(|since| record.timestamp >= since)
+    }
+}
+
+/// Keeps the most recent records of a `Sink::Memory`, for in-game consoles
+/// and tests. Clones share the same records.
+#[derive(Clone)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Adds `record`, dropping the oldest one when full.
+    pub fn push(&self, record: LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut records = self.lock();
+
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+
+        records.push_back(record);
+    }
+
+    /// Every record kept, oldest first.
+    pub fn get_records(&self) -> Vec<LogRecord> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Records matching `query`, oldest first.
+    pub fn query(&self, query: &LogQuery) -> Vec<LogRecord> {
+        let records = self.lock();
+        let mut matching: Vec<_> = records
+            .iter()
+            .filter(|record| query.matches(record))
+            .cloned()
+            .collect();
+
+        if let Some(count) = query.last {
+            matching.drain(..matching.len().saturating_sub(count));
+        }
+
+        matching
+    }
+
+    /// Whether any record matches `query`.
+    pub fn contains(&self, query: &LogQuery) -> bool {
+        self.lock().iter().any(|record| query.matches(record))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<LogRecord>> {
+        self.records.lock().expect("Failed to lock log buffer")
+    }
+}
+
+impl fmt::Debug for LogBuffer {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        formater
+            .debug_struct("LogBuffer")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl PartialEq for LogBuffer {
+    /// Buffers are equal when they share their records.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.records, &other.records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_most_recent_records() {
+        let buffer = LogBuffer::new(2);
+
+        buffer.push(LogRecord::new(Level::Info, "nain", "first"));
+        buffer.push(LogRecord::new(Level::Warn, "nain", "second"));
+        buffer.push(LogRecord::new(Level::Error, "nain", "third"));
+
+        let messages: Vec<_> = buffer
+            .get_records()
+            .iter()
+            .map(|record| record.get_message().to_string())
+            .collect();
+
+        assert_eq!(messages, vec!["second", "third"]);
+        assert_eq!(buffer.clone().len(), 2);
+    }
+
+    #[test]
+    fn queries_records() {
+        let buffer = LogBuffer::new(10);
+
+        buffer.push(LogRecord::new(
+            Level::Debug,
+            "nain_window::backend",
+            "Using glfw",
+        ));
+        buffer.push(LogRecord::new(
+            Level::Warn,
+            "nain_events::event_bus",
+            "Invalid bus",
+        ));
+        buffer.push(LogRecord::new(Level::Error, "sandbox", "Invalid save"));
+        buffer.push(LogRecord::new(Level::Warn, "sandbox::ui", "Missing font"));
+
+        let count = |query: LogQuery| buffer.query(&query).len();
+
+        assert_eq!(count(LogQuery::new().level(Level::Warn)), 3);
+        assert_eq!(count(LogQuery::new().target("sandbox")), 2);
+        assert_eq!(count(LogQuery::new().target("nain_window")), 1);
+        assert_eq!(count(LogQuery::new().target(CORE_TARGET)), 2);
+        assert_eq!(count(LogQuery::new().contains("Invalid")), 2);
+        assert_eq!(
+            buffer.query(&LogQuery::new().target(CLIENT_TARGET).last(1))[0].get_message(),
+            "Missing font"
+        );
+        assert!(!buffer.contains(&LogQuery::new().level(Level::Error).target("nain")));
+    }
+}
@@ -0,0 +1,560 @@
+use crate::buffer::{LogBuffer, LogRecord};
+use crate::json;
+use crate::origin::{Origin, CLIENT_TARGET, CORE_TARGET};
+use crate::rotation::{RotatingFile, Rotation};
+use chrono::format::{Item, StrftimeItems};
+use fern::colors::{Color, ColoredLevelConfig};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::{env, fmt, fs, io};
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+/// Whether `LOGGER` is the process logger, even if building its dispatch
+/// failed since.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static LOGGER: Logger = Logger;
+static DISPATCH: OnceLock<Box<dyn Log>> = OnceLock::new();
+
+/// Forwards to the dispatch built by `apply`, so the process logger can be
+/// claimed before any log file is opened.
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        DISPATCH
+            .get()
+            .is_some_and(|dispatch| dispatch.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(dispatch) = DISPATCH.get() {
+            dispatch.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(dispatch) = DISPATCH.get() {
+            dispatch.flush();
+        }
+    }
+}
+
+/// Where log records are written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sink {
+    Stdout,
+    Stderr,
+    /// Appends to the file, creating it and its directories as needed. Files
+    /// are rotated according to the config's `Rotation`, if any.
+    File(PathBuf),
+    /// Keeps the most recent records in memory.
+    Memory(LogBuffer),
+}
+
+/// How a sink writes records. Memory sinks keep them unformatted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One line per record for people to read, colored on terminals.
+    #[default]
+    Text,
+    /// One JSON object per line, with the record's location and key-value
+    /// fields.
+    Json,
+}
+
+/// Logger settings, applied once with `apply`. Defaults to every record on
+/// stdout and in `log.log`.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    levels: Levels,
+    colors: bool,
+    timestamp_format: String,
+    sinks: Vec<(Sink, LogFormat)>,
+    rotation: Option<Rotation>,
+    /// Problems found while reading the environment, logged once applied.
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Levels {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Io(io::Error),
+    InvalidFilter(String),
+    InvalidTimestampFormat(String),
+    /// A logger is already installed, by an earlier `apply` or another crate.
+    AlreadyInitialized,
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogError::Io(error) => write!(formater, "Cannot open log file: {}", error),
+            LogError::InvalidFilter(filter) => write!(formater, "Invalid log filter: '{}'", filter),
+            LogError::InvalidTimestampFormat(format) => {
+                write!(formater, "Invalid log timestamp format: '{}'", format)
+            }
+            LogError::AlreadyInitialized => write!(formater, "Logger is already initialized"),
+        }
+    }
+}
+
+impl Error for LogError {}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            levels: Levels {
+                default: LevelFilter::Trace,
+                targets: vec![],
+            },
+            colors: true,
+            timestamp_format: "[%Y-%m-%d][%H:%M:%S]".to_string(),
+            sinks: vec![
+                (Sink::Stdout, LogFormat::Text),
+                (Sink::File(PathBuf::from("log.log")), LogFormat::Text),
+            ],
+            rotation: None,
+            warnings: vec![],
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The default config, filtered by `NAIN_LOG` if set. An invalid value is
+    /// ignored, with a warning once the logger is up.
+    pub fn from_env() -> Self {
+        match env::var("NAIN_LOG") {
+            Ok(filters) => Self::default().filters(&filters).unwrap_or_else(|error| {
+                let mut config = Self::default();
+                config
+                    .warnings
+                    .push(format!("Ignoring NAIN_LOG: {}", error));
+                config
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Level of records without a more specific one.
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.levels.default = level;
+        self
+    }
+
+    /// Level of records from `target` and the modules inside it. The `NAIN`
+    /// and `APP` targets also cover the whole engine and client sides.
+    pub fn target_level<T: Into<String>>(mut self, target: T, level: LevelFilter) -> Self {
+        let target = target.into();
+
+        self.levels
+            .targets
+            .retain(|(existing, _)| *existing != target);
+        self.levels.targets.push((target, level));
+        self
+    }
+
+    /// Level of engine records, see `Origin`.
+    pub fn core_level(self, level: LevelFilter) -> Self {
+        self.target_level(CORE_TARGET, level)
+    }
+
+    /// Level of client records, see `Origin`.
+    pub fn client_level(self, level: LevelFilter) -> Self {
+        self.target_level(CLIENT_TARGET, level)
+    }
+
+    /// Applies comma separated filters, in the `NAIN_LOG` syntax: a bare level
+    /// sets the default level, `target=level` the level of a target, e.g.
+    /// `warn,nain_window=debug`.
+    pub fn filters(mut self, filters: &str) -> Result<Self, LogError> {
+        for filter in filters.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let invalid = || LogError::InvalidFilter(filter.to_string());
+
+            match filter.split_once('=') {
+                Some((target, level)) => {
+                    let level = LevelFilter::from_str(level.trim()).map_err(|_| invalid())?;
+
+                    if target.trim().is_empty() {
+                        return Err(invalid());
+                    }
+
+                    self = self.target_level(target.trim(), level);
+                }
+                None => {
+                    self.levels.default = LevelFilter::from_str(filter).map_err(|_| invalid())?
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Whether levels are colored on stdout and stderr. Files never are.
+    pub fn colors(mut self, enabled: bool) -> Self {
+        self.colors = enabled;
+        self
+    }
+
+    /// `chrono` format of the timestamp starting each line.
+    pub fn timestamp_format<T: Into<String>>(mut self, format: T) -> Self {
+        self.timestamp_format = format.into();
+        self
+    }
+
+    /// Writes to `path` instead of the current log files, in their format.
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        let format = self
+            .sinks
+            .iter()
+            .find(|(sink, _)| matches!(sink, Sink::File(_)))
+            .map_or(LogFormat::Text, |(_, format)| *format);
+
+        self.sinks
+            .retain(|(sink, _)| !matches!(sink, Sink::File(_)));
+        self.sinks.push((Sink::File(path.into()), format));
+        self
+    }
+
+    /// Format of the file sinks.
+    pub fn file_format(mut self, format: LogFormat) -> Self {
+        for (sink, sink_format) in self.sinks.iter_mut() {
+            if let Sink::File(_) = sink {
+                *sink_format = format;
+            }
+        }
+
+        self
+    }
+
+    pub fn sink(self, sink: Sink) -> Self {
+        self.sink_with_format(sink, LogFormat::Text)
+    }
+
+    pub fn sink_with_format(mut self, sink: Sink, format: LogFormat) -> Self {
+        self.sinks.push((sink, format));
+        self
+    }
+
+    /// Replaces the sinks with `sinks`, all in the text format.
+    pub fn sinks(mut self, sinks: Vec<Sink>) -> Self {
+        self.sinks = sinks
+            .into_iter()
+            .map(|sink| (sink, LogFormat::Text))
+            .collect();
+        self
+    }
+
+    /// Rotates the file sinks, which otherwise grow forever.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Level applied to records from `target`.
+    pub fn get_level(&self, target: &str) -> LevelFilter {
+        self.levels.get(target)
+    }
+
+    pub fn get_sinks(&self) -> &[(Sink, LogFormat)] {
+        &self.sinks
+    }
+
+    /// Installs the logger. Only the first call in a process can succeed,
+    /// later ones return `LogError::AlreadyInitialized` and change nothing.
+    pub fn apply(self) -> Result<(), LogError> {
+        if StrftimeItems::new(&self.timestamp_format).any(|item| item == Item::Error) {
+            return Err(LogError::InvalidTimestampFormat(self.timestamp_format));
+        }
+
+        if INITIALIZED
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(LogError::AlreadyInitialized);
+        }
+
+        // Claimed before opening the files, as per launch rotation moves the
+        // live log aside. Another crate's logger stays for good.
+        if !INSTALLED.load(Ordering::SeqCst) {
+            log::set_logger(&LOGGER).map_err(|_| LogError::AlreadyInitialized)?;
+            INSTALLED.store(true, Ordering::SeqCst);
+        }
+
+        let dispatch = match self.dispatch() {
+            Ok(dispatch) => dispatch,
+            Err(error) => {
+                INITIALIZED.store(false, Ordering::SeqCst);
+                return Err(error);
+            }
+        };
+        let (level, logger) = dispatch.into_log();
+
+        // Only the call that set `INITIALIZED` gets here.
+        let _ = DISPATCH.set(logger);
+        log::set_max_level(level);
+
+        for warning in &self.warnings {
+            crate::core_warn!("{}", warning);
+        }
+
+        Ok(())
+    }
+
+    fn dispatch(&self) -> Result<fern::Dispatch, LogError> {
+        let levels = self.levels.clone();
+        let mut dispatch = fern::Dispatch::new()
+            .level(self.levels.max())
+            .filter(move |metadata| metadata.level() <= levels.get(metadata.target()));
+
+        for (sink, format) in &self.sinks {
+            dispatch = dispatch.chain(self.sink_dispatch(sink, *format)?);
+        }
+
+        Ok(dispatch)
+    }
+
+    fn sink_dispatch(&self, sink: &Sink, format: LogFormat) -> Result<fern::Dispatch, LogError> {
+        let (output, colors): (fern::Output, bool) = match sink {
+            Sink::Stdout => (io::stdout().into(), self.colors),
+            Sink::Stderr => (io::stderr().into(), self.colors),
+            Sink::File(path) => (self.open_file(path)?, false),
+            Sink::Memory(buffer) => {
+                // Kept unformatted, the buffer has its own fields.
+                let buffer = buffer.clone();
+                let output = fern::Output::call(move |record| {
+                    buffer.push(LogRecord::new(
+                        record.level(),
+                        record.target(),
+                        record.args().to_string(),
+                    ))
+                });
+
+                return Ok(fern::Dispatch::new().chain(output));
+            }
+        };
+
+        if format == LogFormat::Json {
+            let dispatch = fern::Dispatch::new().format(|out, _, record| {
+                out.finish(format_args!(
+                    "{}",
+                    json::format_record(record, chrono::Local::now())
+                ))
+            });
+
+            return Ok(dispatch.chain(output));
+        }
+
+        let level_colors = ColoredLevelConfig::new()
+            .info(Color::Green)
+            .debug(Color::Yellow)
+            .trace(Color::BrightBlue)
+            .error(Color::BrightRed);
+        let timestamp_format = self.timestamp_format.clone();
+
+        let dispatch = fern::Dispatch::new().format(move |out, message, record| {
+            let timestamp = chrono::Local::now().format(&timestamp_format);
+            let origin = Origin::of(record.target());
+            // Records from the side macros are located by module instead.
+            let location = match record.target() {
+                CORE_TARGET | CLIENT_TARGET => record.module_path().unwrap_or_default(),
+                target => target,
+            };
+
+            if colors {
+                let origin_color = match origin {
+                    Origin::Core => Color::Cyan,
+                    Origin::Client => Color::Magenta,
+                };
+
+                out.finish(format_args!(
+                    "{} \x1B[{}m[{}]\x1B[0m [{}] [{}] {}",
+                    timestamp,
+                    origin_color.to_fg_str(),
+                    origin.get_target(),
+                    location,
+                    level_colors.color(record.level()),
+                    message
+                ))
+            } else {
+                out.finish(format_args!(
+                    "{} [{}] [{}] [{}] {}",
+                    timestamp,
+                    origin.get_target(),
+                    location,
+                    record.level(),
+                    message
+                ))
+            }
+        });
+
+        Ok(dispatch.chain(output))
+    }
+
+    fn open_file(&self, path: &Path) -> Result<fern::Output, LogError> {
+        if let Some(directory) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(directory).map_err(LogError::Io)?;
+        }
+
+        let output = match &self.rotation {
+            Some(rotation) => {
+                let file = RotatingFile::open(path, rotation.clone()).map_err(LogError::Io)?;
+
+                fern::Output::from(Box::new(file) as Box<dyn Write + Send>)
+            }
+            None => fern::log_file(path).map_err(LogError::Io)?.into(),
+        };
+
+        Ok(output)
+    }
+}
+
+impl Levels {
+    /// The most specific target level wins, then the level of the side the
+    /// target is on.
+    fn get(&self, target: &str) -> LevelFilter {
+        let origin = Origin::of(target).get_target();
+
+        self.targets
+            .iter()
+            .filter(|(filter, _)| is_within(target, filter))
+            .max_by_key(|(filter, _)| filter.len())
+            .or_else(|| self.targets.iter().find(|(filter, _)| filter == origin))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+/// Whether `target` is `filter` or one of its modules.
+pub(crate) fn is_within(target: &str, filter: &str) -> bool {
+    target
+        .strip_prefix(filter)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters() {
+        let config = LogConfig::new()
+            .filters("warn, nain_window=debug,nain_window::platforms=off")
+            .unwrap();
+
+        assert_eq!(config.get_level("nain"), LevelFilter::Warn);
+        assert_eq!(config.get_level("nain_window"), LevelFilter::Debug);
+        assert_eq!(config.get_level("nain_window::backend"), LevelFilter::Debug);
+        assert_eq!(
+            config.get_level("nain_window::platforms::glfw"),
+            LevelFilter::Off
+        );
+        assert_eq!(config.get_level("nain_windows"), LevelFilter::Warn);
+        assert_eq!(config.get_level("sandbox"), LevelFilter::Warn);
+
+        for invalid in &["loud", "nain_window=", "=info"] {
+            assert!(
+                matches!(
+                    LogConfig::new().filters(invalid),
+                    Err(LogError::InvalidFilter(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn filters_core_and_client_sides() {
+        let config = LogConfig::new()
+            .level(LevelFilter::Info)
+            .core_level(LevelFilter::Warn)
+            .client_level(LevelFilter::Debug)
+            .target_level("nain_window", LevelFilter::Trace);
+
+        assert_eq!(config.get_level("NAIN"), LevelFilter::Warn);
+        assert_eq!(
+            config.get_level("nain_events::event_bus"),
+            LevelFilter::Warn
+        );
+        assert_eq!(config.get_level("nain_window::backend"), LevelFilter::Trace);
+        assert_eq!(config.get_level("APP"), LevelFilter::Debug);
+        assert_eq!(config.get_level("sandbox"), LevelFilter::Debug);
+        assert_eq!(config.levels.max(), LevelFilter::Trace);
+
+        let config = LogConfig::new().filters("info,APP=off").unwrap();
+        assert_eq!(config.get_level("sandbox::game"), LevelFilter::Off);
+        assert_eq!(config.get_level("nain"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn replaces_the_file_sink() {
+        let config = LogConfig::new()
+            .file_format(LogFormat::Json)
+            .file("logs/game.log")
+            .sink(Sink::Stderr);
+
+        assert_eq!(
+            config.get_sinks(),
+            &[
+                (Sink::Stdout, LogFormat::Text),
+                (Sink::File(PathBuf::from("logs/game.log")), LogFormat::Json),
+                (Sink::Stderr, LogFormat::Text),
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_only_once() {
+        let invalid = LogConfig::new().sinks(vec![]).timestamp_format("%Q");
+        assert!(matches!(
+            invalid.apply(),
+            Err(LogError::InvalidTimestampFormat(_))
+        ));
+
+        let directory = std::env::temp_dir().join(format!("nain_log_apply_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("blocker"), "").unwrap();
+        let unopenable =
+            LogConfig::new().sinks(vec![Sink::File(directory.join("blocker/game.log"))]);
+        assert!(matches!(unopenable.apply(), Err(LogError::Io(_))));
+
+        assert!(LogConfig::new().sinks(vec![]).apply().is_ok());
+
+        let path = directory.join("game.log");
+        fs::write(&path, "live\n").unwrap();
+        assert!(matches!(
+            LogConfig::new()
+                .sinks(vec![Sink::File(path.clone())])
+                .rotation(Rotation::new())
+                .apply(),
+            Err(LogError::AlreadyInitialized)
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "live\n");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+use crate::origin::Origin;
+use chrono::{DateTime, Local, SecondsFormat};
+use log::kv::{self, Key, Value, VisitSource};
+use log::Record;
+use serde_json::{json, Map};
+
+/// Collects the key-value fields of a record.
+struct Fields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = serde_json::to_value(&value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// One JSON object per record, without the line break.
+pub(crate) fn format_record(record: &Record, timestamp: DateTime<Local>) -> String {
+    let mut fields = Fields(Map::new());
+
+    // Visiting only fails when the visitor does, and this one never does.
+    let _ = record.key_values().visit(&mut fields);
+
+    json!({
+        "timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Millis, false),
+        "level": record.level().as_str(),
+        "origin": Origin::of(record.target()).get_target(),
+        "target": record.target(),
+        "module": record.module_path(),
+        "file": record.file(),
+        "line": record.line(),
+        "message": record.args().to_string(),
+        "fields": fields.0,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn formats_records_as_json() {
+        let fields = [("frame", 42)];
+        let line = format_record(
+            &Record::builder()
+                .args(format_args!("Saved \"{}\"", "slot 1"))
+                .level(Level::Info)
+                .target("sandbox::save")
+                .module_path(Some("sandbox::save"))
+                .file(Some("src/save.rs"))
+                .line(Some(12))
+                .key_values(&fields)
+                .build(),
+            Local::now(),
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["origin"], "APP");
+        assert_eq!(json["target"], "sandbox::save");
+        assert_eq!(json["file"], "src/save.rs");
+        assert_eq!(json["line"], 12);
+        assert_eq!(json["message"], "Saved \"slot 1\"");
+        assert_eq!(json["fields"]["frame"], 42);
+        assert!(json["timestamp"].is_string());
+    }
+}
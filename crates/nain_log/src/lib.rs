@@ -1,30 +1,41 @@
-use chrono;
-use fern::colors::{Color, ColoredLevelConfig};
-use log::LevelFilter;
-
-pub use log::{debug, error, info, trace, warn};
-
-pub fn init() -> Result<(), fern::InitError> {
-    let log_colors = ColoredLevelConfig::new()
-        .info(Color::Green)
-        .debug(Color::Yellow)
-        .trace(Color::BrightBlue)
-        .error(Color::BrightRed);
-
-    fern::Dispatch::new()
-        .format(move |out, message, record| {
-            out.finish(format_args!(
-                "{} [{}] [{}] {}",
-                chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
-                record.target(),
-                log_colors.color(record.level()),
-                message
-            ))
-        })
-        .level(LevelFilter::Trace)
-        .chain(std::io::stdout())
-        .chain(fern::log_file("log.log")?)
+pub use self::buffer::{LogBuffer, LogQuery, LogRecord};
+pub use self::config::{LogConfig, LogError, LogFormat, Sink};
+pub use self::origin::{Origin, CLIENT_TARGET, CORE_TARGET};
+pub use self::rotation::Rotation;
+pub use log::{debug, error, info, trace, warn, Level, LevelFilter};
+
+use std::sync::Mutex;
+
+mod buffer;
+mod config;
+mod json;
+mod macros;
+mod origin;
+mod rotation;
+
+static CAPTURE: Mutex<Option<LogBuffer>> = Mutex::new(None);
+
+/// Installs the default logger, filtered by `NAIN_LOG`.
+pub fn init() -> Result<(), LogError> {
+    LogConfig::from_env().apply()
+}
+
+/// Installs a logger keeping every record in memory, for tests to assert on
+/// what was logged. Later calls return the same buffer, so tests running in
+/// parallel should query by something unique to them.
+pub fn capture() -> Result<LogBuffer, LogError> {
+    let mut capture = CAPTURE.lock().expect("Failed to lock log capture");
+
+    if let Some(buffer) = &*capture {
+        return Ok(buffer.clone());
+    }
+
+    let buffer = LogBuffer::new(4096);
+
+    LogConfig::new()
+        .sinks(vec![Sink::Memory(buffer.clone())])
         .apply()?;
 
-    Ok(())
+    *capture = Some(buffer.clone());
+    Ok(buffer)
 }
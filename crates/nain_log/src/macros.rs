@@ -0,0 +1,79 @@
+/// `trace!` under the `NAIN` target.
+#[macro_export]
+macro_rules! core_trace {
+    ($($arg:tt)+) => {
+        $crate::trace!(target: $crate::CORE_TARGET, $($arg)+)
+    };
+}
+
+/// `debug!` under the `NAIN` target.
+#[macro_export]
+macro_rules! core_debug {
+    ($($arg:tt)+) => {
+        $crate::debug!(target: $crate::CORE_TARGET, $($arg)+)
+    };
+}
+
+/// `info!` under the `NAIN` target.
+#[macro_export]
+macro_rules! core_info {
+    ($($arg:tt)+) => {
+        $crate::info!(target: $crate::CORE_TARGET, $($arg)+)
+    };
+}
+
+/// `warn!` under the `NAIN` target.
+#[macro_export]
+macro_rules! core_warn {
+    ($($arg:tt)+) => {
+        $crate::warn!(target: $crate::CORE_TARGET, $($arg)+)
+    };
+}
+
+/// `error!` under the `NAIN` target.
+#[macro_export]
+macro_rules! core_error {
+    ($($arg:tt)+) => {
+        $crate::error!(target: $crate::CORE_TARGET, $($arg)+)
+    };
+}
+
+/// `trace!` under the `APP` target.
+#[macro_export]
+macro_rules! client_trace {
+    ($($arg:tt)+) => {
+        $crate::trace!(target: $crate::CLIENT_TARGET, $($arg)+)
+    };
+}
+
+/// `debug!` under the `APP` target.
+#[macro_export]
+macro_rules! client_debug {
+    ($($arg:tt)+) => {
+        $crate::debug!(target: $crate::CLIENT_TARGET, $($arg)+)
+    };
+}
+
+/// `info!` under the `APP` target.
+#[macro_export]
+macro_rules! client_info {
+    ($($arg:tt)+) => {
+        $crate::info!(target: $crate::CLIENT_TARGET, $($arg)+)
+    };
+}
+
+/// `warn!` under the `APP` target.
+#[macro_export]
+macro_rules! client_warn {
+    ($($arg:tt)+) => {
+        $crate::warn!(target: $crate::CLIENT_TARGET, $($arg)+)
+    };
+}
+
+/// `error!` under the `APP` target.
+#[macro_export]
+macro_rules! client_error {
+    ($($arg:tt)+) => {
+        $crate::error!(target: $crate::CLIENT_TARGET, $($arg)+)
+    };
+}
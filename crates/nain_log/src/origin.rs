@@ -0,0 +1,49 @@
+/// Target of records logged through the `core_*` macros.
+pub const CORE_TARGET: &str = "NAIN";
+/// Target of records logged through the `client_*` macros.
+pub const CLIENT_TARGET: &str = "APP";
+
+/// Which side of the engine a record comes from. Each side has its own level
+/// and prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Core,
+    Client,
+}
+
+impl Origin {
+    /// Records from the `NAIN` target or from the engine crates (`nain` and
+    /// `nain_*`) are core, everything else is client code.
+    pub fn of(target: &str) -> Self {
+        let root = target.split("::").next().unwrap_or_default();
+
+        if root == CORE_TARGET || root == "nain" || root.starts_with("nain_") {
+            Origin::Core
+        } else {
+            Origin::Client
+        }
+    }
+
+    /// The target whose level applies to this side.
+    pub fn get_target(&self) -> &'static str {
+        match self {
+            Origin::Core => CORE_TARGET,
+            Origin::Client => CLIENT_TARGET,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_engine_records_from_client_ones() {
+        assert_eq!(Origin::of("NAIN"), Origin::Core);
+        assert_eq!(Origin::of("nain"), Origin::Core);
+        assert_eq!(Origin::of("nain_window::platforms::glfw"), Origin::Core);
+        assert_eq!(Origin::of("APP"), Origin::Client);
+        assert_eq!(Origin::of("sandbox"), Origin::Client);
+        assert_eq!(Origin::of("nainja::combat"), Origin::Client);
+    }
+}
@@ -0,0 +1,457 @@
+use chrono::{Local, NaiveDateTime};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S%.3f";
+const TIMESTAMP_LENGTH: usize = 23;
+
+static CLEAN_UP: Mutex<()> = Mutex::new(());
+
+/// How file sinks are rotated. A rotated file is renamed after the time it
+/// was rotated, e.g. `game.log` to `game.2020-01-31_12-00-00.000.log`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    max_size: Option<u64>,
+    per_launch: bool,
+    keep: usize,
+    compress: bool,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            max_size: None,
+            per_launch: true,
+            keep: 5,
+            compress: false,
+        }
+    }
+}
+
+impl Rotation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rotates once the file holds `bytes`, after the line being written.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Whether every launch starts a new file.
+    pub fn per_launch(mut self, enabled: bool) -> Self {
+        self.per_launch = enabled;
+        self
+    }
+
+    /// Number of rotated files to keep, older ones are deleted.
+    pub fn keep(mut self, count: usize) -> Self {
+        self.keep = count;
+        self
+    }
+
+    /// Whether rotated files are gzipped. Compression runs on a background
+    /// thread, so logging goes on meanwhile.
+    pub fn compress(mut self, enabled: bool) -> Self {
+        self.compress = enabled;
+        self
+    }
+}
+
+/// Log file that moves itself aside according to a `Rotation`.
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    /// Only missing if reopening after a rotation failed.
+    file: Option<File>,
+    size: u64,
+    at_line_start: bool,
+    /// Size to rotate at, pushed back when moving the file aside failed.
+    limit: Option<u64>,
+    /// Compressions and clean ups still running in the background.
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: &Path, rotation: Rotation) -> io::Result<Self> {
+        let is_empty = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+        let mut file = Self {
+            path: path.to_path_buf(),
+            limit: rotation.max_size,
+            rotation,
+            file: None,
+            size: 0,
+            at_line_start: true,
+            workers: vec![],
+        };
+
+        if file.rotation.per_launch && !is_empty {
+            file.archive();
+        }
+
+        file.reopen()?;
+        Ok(file)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Closed first, as open files cannot be renamed everywhere.
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+
+        self.archive();
+        self.reopen()
+    }
+
+    /// Moves the file aside, then compresses and prunes the rotated files.
+    /// Errors are reported on stderr, as the logger cannot log.
+    fn archive(&mut self) {
+        let archived = match move_aside(&self.path) {
+            Ok(archived) => archived,
+            Err(error) => {
+                eprintln!(
+                    "Failed to rotate log file '{}': {}",
+                    self.path.display(),
+                    error
+                );
+
+                // Retried once the file grew by another `max_size`.
+                let size = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+                self.limit = self.rotation.max_size.map(|max_size| size + max_size);
+                return;
+            }
+        };
+
+        self.limit = self.rotation.max_size;
+
+        let path = self.path.clone();
+        let rotation = self.rotation.clone();
+
+        if rotation.compress {
+            // Done outside the writer, so logging goes on meanwhile.
+            self.workers.retain(|worker| !worker.is_finished());
+            self.workers
+                .push(thread::spawn(move || clean_up(&path, &archived, &rotation)));
+        } else {
+            clean_up(&path, &archived, &rotation);
+        }
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let file = open_log(&self.path)?;
+
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let is_full = self.limit.is_some_and(|limit| self.size >= limit);
+
+        if is_full && self.at_line_start {
+            self.rotate()?;
+        } else if self.file.is_none() {
+            self.reopen()?;
+        }
+
+        let written = match &mut self.file {
+            Some(file) => file.write(buffer)?,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Log file is closed",
+                ))
+            }
+        };
+
+        self.size += written as u64;
+        if written > 0 {
+            self.at_line_start = buffer[written - 1] == b'\n';
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Splits `game.log` into `game` and `.log`.
+fn split_name(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (stem, extension)
+}
+
+/// Renames the file at `path` after the current time, and returns its new
+/// path.
+fn move_aside(path: &Path) -> io::Result<PathBuf> {
+    let (stem, extension) = split_name(path);
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut archived = path.with_file_name(format!("{}.{}{}", stem, timestamp, extension));
+    let mut index = 0;
+
+    // Several rotations can happen within a millisecond.
+    while archived.exists() || gzipped(&archived).exists() {
+        index += 1;
+        archived = path.with_file_name(format!("{}.{}-{}{}", stem, timestamp, index, extension));
+    }
+
+    fs::rename(path, &archived)?;
+    Ok(archived)
+}
+
+/// Compresses `archived` if `rotation` asks to, then deletes the oldest
+/// rotated files beyond what it keeps. Failures are reported and do not stop
+/// later rotations.
+fn clean_up(path: &Path, archived: &Path, rotation: &Rotation) {
+    // Clean ups of the same log would otherwise race over its files.
+    let _guard = CLEAN_UP.lock().unwrap_or_else(|error| error.into_inner());
+
+    if rotation.compress {
+        if let Err(error) = compress(archived) {
+            eprintln!(
+                "Failed to compress rotated log file '{}': {}",
+                archived.display(),
+                error
+            );
+        }
+    }
+
+    if let Err(error) = prune(path, rotation.keep) {
+        eprintln!(
+            "Failed to delete old log files of '{}': {}",
+            path.display(),
+            error
+        );
+    }
+}
+
+/// Deletes the oldest rotated files of the log at `path` beyond `keep`,
+/// going on past failures and returning the first one.
+fn prune(path: &Path, keep: usize) -> io::Result<()> {
+    let mut result = Ok(());
+
+    for old in rotated_files(path)?.iter().rev().skip(keep) {
+        match fs::remove_file(old) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound && result.is_ok() => {
+                result = Err(error);
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+fn gzipped(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(gzipped(path))?, Compression::default());
+
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+
+    fs::remove_file(path)
+}
+
+/// Rotated files of the log at `path`, oldest first.
+fn rotated_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let (stem, extension) = split_name(path);
+    let prefix = format!("{}.", stem);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut files = vec![];
+
+    for entry in fs::read_dir(directory)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let rest = match name.strip_prefix(&prefix) {
+            Some(rest)
+                if rest.len() > TIMESTAMP_LENGTH && rest.is_char_boundary(TIMESTAMP_LENGTH) =>
+            {
+                rest
+            }
+            _ => continue,
+        };
+        let (timestamp, suffix) = rest.split_at(TIMESTAMP_LENGTH);
+
+        if NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).is_err() {
+            continue;
+        }
+
+        // What follows is an optional `-index`, then the extension.
+        let (index, suffix) = match suffix.strip_prefix('-') {
+            Some(suffix) => {
+                let digits = suffix.len()
+                    - suffix
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .len();
+
+                match suffix[..digits].parse::<u32>() {
+                    Ok(index) => (index, &suffix[digits..]),
+                    Err(_) => continue,
+                }
+            }
+            None => (0, suffix),
+        };
+
+        if suffix == extension || suffix == format!("{}.gz", extension) {
+            files.push((timestamp.to_string(), index, directory.join(&name)));
+        }
+    }
+
+    files.sort();
+    Ok(files.into_iter().map(|(_, _, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("nain_log_{}_{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn rotates_on_launch_and_keeps_the_latest() {
+        let directory = temp_dir("launch");
+        let path = directory.join("game.log");
+        fs::write(directory.join("game.notes.log"), "unrelated").unwrap();
+
+        for launch in 0..4 {
+            let mut file = RotatingFile::open(&path, Rotation::new().keep(2)).unwrap();
+            writeln!(file, "launch {}", launch).unwrap();
+        }
+
+        let rotated = rotated_files(&path).unwrap();
+
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "launch 1\n");
+        assert_eq!(fs::read_to_string(&rotated[1]).unwrap(), "launch 2\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "launch 3\n");
+        assert!(directory.join("game.notes.log").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rotates_full_files_between_lines() {
+        let directory = temp_dir("size");
+        let path = directory.join("game.log");
+        let rotation = Rotation::new().max_size(8).compress(true);
+        let mut file = RotatingFile::open(&path, rotation).unwrap();
+
+        for line in &["first", "second", "third"] {
+            write!(file, "{}", line).unwrap();
+            writeln!(file, " line").unwrap();
+        }
+
+        // Waits for the background compressions.
+        drop(file);
+
+        let rotated = rotated_files(&path).unwrap();
+        let mut first = String::new();
+        GzDecoder::new(File::open(&rotated[0]).unwrap())
+            .read_to_string(&mut first)
+            .unwrap();
+
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(first, "first line\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third line\n");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn retries_failed_rotations() {
+        let directory = temp_dir("retry");
+        let path = directory.join("game.log");
+        let mut file = RotatingFile::open(&path, Rotation::new().max_size(8)).unwrap();
+
+        writeln!(file, "first line").unwrap();
+        // Renaming a file that is gone fails, the next line starts a new one.
+        fs::remove_file(&path).unwrap();
+        writeln!(file, "second line").unwrap();
+
+        assert!(rotated_files(&path).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second line\n");
+
+        writeln!(file, "third line").unwrap();
+
+        let rotated = rotated_files(&path).unwrap();
+
+        assert_eq!(rotated.len(), 1);
+        assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "second line\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third line\n");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn keeps_rotating_when_pruning_fails() {
+        let directory = temp_dir("prune");
+        let path = directory.join("game.log");
+        // Pruning cannot remove a directory, so every clean up fails.
+        fs::create_dir(directory.join("game.2000-01-01_00-00-00.000.log")).unwrap();
+
+        let rotation = Rotation::new().max_size(8).keep(1);
+        let mut file = RotatingFile::open(&path, rotation).unwrap();
+
+        for line in &["first", "second", "third"] {
+            writeln!(file, "{} line", line).unwrap();
+        }
+
+        let rotated = rotated_files(&path).unwrap();
+
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(fs::read_to_string(&rotated[1]).unwrap(), "second line\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third line\n");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(feature = "glfw")]
+use crate::platforms::GlfwWindow;
+use crate::platforms::{HeadlessInput, HeadlessWindow};
+use crate::{Window, WindowProps};
+use nain_log as log;
+use std::env;
+
+/// Platform implementation used to create a window.
+#[derive(Clone)]
+pub enum WindowBackend {
+    #[cfg(feature = "glfw")]
+    Glfw,
+    Headless(HeadlessInput),
+}
+
+impl Default for WindowBackend {
+    /// GLFW when compiled in, unless the `headless` feature is enabled.
+    fn default() -> Self {
+        #[cfg(all(feature = "glfw", not(feature = "headless")))]
+        return WindowBackend::Glfw;
+
+        #[cfg(any(feature = "headless", not(feature = "glfw")))]
+        return WindowBackend::Headless(HeadlessInput::default());
+    }
+}
+
+impl WindowBackend {
+    /// Picks the backend named by `NAIN_WINDOW_BACKEND` (`glfw` or `headless`),
+    /// falling back to the default.
+    pub fn from_env() -> Self {
+        match env::var("NAIN_WINDOW_BACKEND") {
+            Ok(name) => Self::from_name(&name).unwrap_or_else(|| {
+                log::warn!("Unknown window backend '{}', using default", name);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            #[cfg(feature = "glfw")]
+            "glfw" => Some(WindowBackend::Glfw),
+            "headless" => Some(WindowBackend::Headless(HeadlessInput::default())),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "glfw")]
+            WindowBackend::Glfw => "glfw",
+            WindowBackend::Headless(_) => "headless",
+        }
+    }
+}
+
+/// Creates a `Window` for a given backend.
+#[derive(Default)]
+pub struct WindowBuilder {
+    props: WindowProps,
+    backend: WindowBackend,
+}
+
+impl WindowBuilder {
+    pub fn new(props: WindowProps) -> Self {
+        Self {
+            props,
+            backend: WindowBackend::default(),
+        }
+    }
+
+    pub fn backend(mut self, backend: WindowBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn build(self) -> Box<dyn Window> {
+        log::debug!("Using {} window backend", self.backend.get_name());
+
+        match self.backend {
+            #[cfg(feature = "glfw")]
+            WindowBackend::Glfw => Box::new(GlfwWindow::new(self.props)),
+            WindowBackend::Headless(input) => {
+                Box::new(HeadlessWindow::with_input(self.props, input))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nain_events::WindowResizeEvent;
+
+    #[test]
+    fn parses_backend_names() {
+        assert_eq!(
+            WindowBackend::from_name("Headless").map(|backend| backend.get_name()),
+            Some("headless")
+        );
+        assert!(WindowBackend::from_name("vulkan").is_none());
+    }
+
+    #[test]
+    fn builds_a_headless_window_sharing_its_input() {
+        let input = HeadlessInput::default();
+        let mut window = WindowBuilder::new(WindowProps::new("Headless", 64, 32))
+            .backend(WindowBackend::Headless(input.clone()))
+            .build();
+
+        assert_eq!(window.get_width(), 64);
+
+        input.push(WindowResizeEvent::new(128, 96));
+        window.on_update();
+
+        assert_eq!(window.get_width(), 128);
+        assert_eq!(window.get_height(), 96);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::WindowIcon;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+    #[default]
+    Normal,
+    /// Invisible while over the window, but otherwise free to move.
+    Hidden,
+    /// Hidden and locked to the window, for camera controls. Movement is
+    /// reported through the `MouseMovedEvent` deltas, unaccelerated where the
+    /// platform supports raw motion.
+    Disabled,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Cursor {
+    #[default]
+    Arrow,
+    IBeam,
+    Crosshair,
+    Hand,
+    HResize,
+    VResize,
+    /// RGBA image, laid out like a window icon. `hotspot` is the click point,
+    /// relative to the top-left corner.
+    Custom {
+        image: WindowIcon,
+        hotspot: (u32, u32),
+    },
+}
@@ -1,6 +1,16 @@
-pub use self::window::{Window as WindowTrait, WindowProps};
+pub use self::backend::{WindowBackend, WindowBuilder};
+pub use self::cursor::{Cursor, CursorMode};
+pub use self::monitor::{MonitorInfo, VideoMode};
+pub use self::window::{
+    EventCallback, Window, WindowIcon, WindowIconError, WindowMode, WindowProps,
+};
 
-pub use self::platforms::WindowsWindow as Window;
+#[cfg(feature = "glfw")]
+pub use self::platforms::GlfwWindow;
+pub use self::platforms::{HeadlessInput, HeadlessWindow, VirtualGamepad};
 
+mod backend;
+mod cursor;
+mod monitor;
 mod platforms;
 mod window;
@@ -0,0 +1,41 @@
+use std::fmt;
+
+/// Resolution and refresh rate a monitor can be driven at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+impl VideoMode {
+    pub fn new(width: u32, height: u32, refresh_rate: u32) -> Self {
+        Self {
+            width,
+            height,
+            refresh_rate,
+        }
+    }
+}
+
+impl fmt::Display for VideoMode {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formater,
+            "{}x{} @ {}Hz",
+            self.width, self.height, self.refresh_rate
+        )
+    }
+}
+
+/// A connected monitor, as reported by `Window::get_monitors`. The primary
+/// monitor is always at index 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    /// Mode the monitor is currently running at.
+    pub video_mode: VideoMode,
+    pub video_modes: Vec<VideoMode>,
+}
@@ -0,0 +1,755 @@
+use crate::{
+    Cursor, CursorMode, EventCallback, MonitorInfo, VideoMode, Window, WindowIcon, WindowMode,
+    WindowProps,
+};
+use glfw::{Action, Context, SwapInterval};
+use nain_events::{
+    Event, GamepadAxis, GamepadAxisMovedEvent, GamepadButton, GamepadButtonPressedEvent,
+    GamepadButtonReleasedEvent, GamepadConnectedEvent, GamepadDisconnectedEvent, KeyCode,
+    KeyPressedEvent, KeyReleasedEvent, KeyTypedEvent, Modifiers, MouseButton,
+    MouseButtonPressedEvent, MouseButtonReleasedEvent, MouseMovedEvent, MouseScrolledEvent,
+    TextInputEvent, WindowCloseEvent, WindowFocusEvent, WindowLostFocusEvent, WindowMovedEvent,
+    WindowResizeEvent,
+};
+use nain_log as log;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+struct WindowData {
+    title: String,
+    width: u32,
+    height: u32,
+    vsync: bool,
+    decorated: bool,
+    mode: WindowMode,
+    monitor: usize,
+    /// Position and size to restore when leaving fullscreen.
+    windowed: (i32, i32, u32, u32),
+    cursor_mode: CursorMode,
+    /// Last cursor position, to report motion deltas.
+    cursor_position: Option<(f64, f64)>,
+    /// Repeats so far for each held key, by scancode.
+    key_repeats: HashMap<glfw::Scancode, u32>,
+    /// Last polled state of each connected gamepad, by joystick id.
+    gamepads: HashMap<u32, glfw::GamepadState>,
+    event_callback: Option<EventCallback>,
+}
+
+pub struct GlfwWindow {
+    window: glfw::Window,
+    data: WindowData,
+    events: std::sync::mpsc::Receiver<(f64, glfw::WindowEvent)>,
+}
+
+impl GlfwWindow {
+    pub fn new(props: WindowProps) -> Self {
+        let glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
+        let mut glfw_window = GlfwWindow::init(props, glfw);
+
+        glfw_window
+            .window
+            .glfw
+            .set_error_callback(Some(glfw::Callback {
+                f: error_callback,
+                data: Cell::new(0),
+            }));
+
+        glfw_window
+    }
+}
+
+impl Window for GlfwWindow {
+    fn get_width(&self) -> u32 {
+        self.data.width
+    }
+
+    fn get_height(&self) -> u32 {
+        self.data.height
+    }
+
+    fn get_title(&self) -> &str {
+        &self.data.title
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+        self.data.title = title.to_string();
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) {
+        // The size is updated once GLFW reports it, after applying limits.
+        self.window.set_size(width as i32, height as i32);
+    }
+
+    fn get_position(&self) -> (i32, i32) {
+        self.window.get_pos()
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        self.window.set_pos(x, y);
+    }
+
+    fn set_vsync(&mut self, enabled: bool) {
+        if enabled {
+            self.window.glfw.set_swap_interval(SwapInterval::Sync(1))
+        } else {
+            self.window.glfw.set_swap_interval(SwapInterval::None)
+        }
+
+        self.data.vsync = enabled;
+    }
+
+    fn is_vsync_enabled(&self) -> bool {
+        self.data.vsync
+    }
+
+    fn set_event_callback(&mut self, callback: EventCallback) {
+        self.data.event_callback = Some(callback);
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.window.set_cursor_mode(match mode {
+            CursorMode::Normal => glfw::CursorMode::Normal,
+            CursorMode::Hidden => glfw::CursorMode::Hidden,
+            CursorMode::Disabled => glfw::CursorMode::Disabled,
+        });
+
+        if self.window.glfw.supports_raw_motion() {
+            self.window
+                .set_raw_mouse_motion(mode == CursorMode::Disabled);
+        }
+
+        // GLFW moves the cursor when capturing it, which is not user motion.
+        self.data.cursor_position = None;
+        self.data.cursor_mode = mode;
+    }
+
+    fn get_cursor_mode(&self) -> CursorMode {
+        self.data.cursor_mode
+    }
+
+    fn set_cursor(&mut self, cursor: Cursor) {
+        let cursor = match cursor {
+            Cursor::Arrow => glfw::Cursor::standard(glfw::StandardCursor::Arrow),
+            Cursor::IBeam => glfw::Cursor::standard(glfw::StandardCursor::IBeam),
+            Cursor::Crosshair => glfw::Cursor::standard(glfw::StandardCursor::Crosshair),
+            Cursor::Hand => glfw::Cursor::standard(glfw::StandardCursor::Hand),
+            Cursor::HResize => glfw::Cursor::standard(glfw::StandardCursor::HResize),
+            Cursor::VResize => glfw::Cursor::standard(glfw::StandardCursor::VResize),
+            Cursor::Custom { image, hotspot } => {
+                glfw::Cursor::create_from_pixels(pixel_image(&image), hotspot.0, hotspot.1)
+            }
+        };
+
+        self.window.set_cursor(Some(cursor));
+    }
+
+    fn get_monitors(&self) -> Vec<MonitorInfo> {
+        let mut glfw = self.window.glfw.clone();
+
+        glfw.with_connected_monitors(|_, monitors| monitors.iter().map(monitor_info).collect())
+    }
+
+    fn set_window_mode(&mut self, mode: WindowMode, monitor: usize) {
+        if self.data.mode == WindowMode::Windowed {
+            let (x, y) = self.window.get_pos();
+            let (width, height) = self.window.get_size();
+            self.data.windowed = (x, y, width as u32, height as u32);
+        }
+
+        if mode == WindowMode::Windowed {
+            let (x, y, width, height) = self.data.windowed;
+
+            self.window.set_decorated(self.data.decorated);
+            self.window
+                .set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+
+            self.data.mode = mode;
+            self.data.monitor = monitor;
+            return;
+        }
+
+        let mut glfw = self.window.glfw.clone();
+        let window = &mut self.window;
+
+        let applied = glfw.with_connected_monitors(|_, monitors| {
+            let index = if monitor < monitors.len() {
+                monitor
+            } else {
+                log::warn!(
+                    "Monitor {} is not connected, using the primary one",
+                    monitor
+                );
+                0
+            };
+            let target = monitors.get(index)?;
+            let current = target.get_video_mode()?;
+
+            match mode {
+                WindowMode::BorderlessFullscreen => {
+                    let (x, y) = target.get_pos();
+
+                    window.set_decorated(false);
+                    window.set_monitor(
+                        glfw::WindowMode::Windowed,
+                        x,
+                        y,
+                        current.width,
+                        current.height,
+                        None,
+                    );
+                }
+                WindowMode::Fullscreen(video_mode) => {
+                    let video_mode = video_mode.unwrap_or_else(|| VideoMode::from(current));
+
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(target),
+                        0,
+                        0,
+                        video_mode.width,
+                        video_mode.height,
+                        Some(video_mode.refresh_rate),
+                    );
+                }
+                WindowMode::Windowed => unreachable!(),
+            }
+
+            Some(index)
+        });
+
+        match applied {
+            Some(index) => {
+                self.data.mode = mode;
+                self.data.monitor = index;
+            }
+            None => log::error!("No monitor available for {:?}", mode),
+        }
+    }
+
+    fn get_window_mode(&self) -> WindowMode {
+        self.data.mode
+    }
+
+    fn get_monitor(&self) -> usize {
+        self.data.monitor
+    }
+
+    fn on_update(&mut self) {
+        self.window.glfw.poll_events();
+        self.window.swap_buffers();
+
+        let mut events = vec![];
+        for (_, event) in glfw::flush_messages(&self.events) {
+            events.push(event);
+        }
+
+        let mut text = String::new();
+
+        for event in events {
+            if let glfw::WindowEvent::Char(character) = event {
+                text.push(character);
+            }
+
+            self.handle_window_events(event);
+        }
+
+        if !text.is_empty() {
+            self.emit(TextInputEvent::new(text));
+        }
+
+        self.poll_gamepads();
+    }
+}
+
+impl GlfwWindow {
+    fn init(props: WindowProps, mut glfw: glfw::Glfw) -> GlfwWindow {
+        let width = props.width;
+        let height = props.height;
+        let title = props.title;
+
+        log::info!("Creating window: {} ({}, {})", title, width, height);
+
+        glfw.window_hint(glfw::WindowHint::Resizable(props.resizable));
+        glfw.window_hint(glfw::WindowHint::Decorated(props.decorated));
+        glfw.window_hint(glfw::WindowHint::TransparentFramebuffer(props.transparent));
+
+        let (mut window, events) = glfw
+            .create_window(width, height, &title, glfw::WindowMode::Windowed)
+            .expect("Failed to create window.");
+
+        window.set_all_polling(true);
+        window.make_current();
+
+        window.set_size_limits(
+            props.min_size.map(|(width, _)| width),
+            props.min_size.map(|(_, height)| height),
+            props.max_size.map(|(width, _)| width),
+            props.max_size.map(|(_, height)| height),
+        );
+
+        if let Some((x, y)) = props.position {
+            window.set_pos(x, y);
+        }
+
+        if let Some(icon) = props.icon {
+            window.set_icon_from_pixels(vec![pixel_image(&icon)]);
+        }
+
+        let (x, y) = window.get_pos();
+
+        let mut glfw_window = GlfwWindow {
+            window,
+            data: WindowData {
+                title,
+                width,
+                height,
+                vsync: true,
+                decorated: props.decorated,
+                mode: WindowMode::Windowed,
+                monitor: props.monitor,
+                cursor_mode: CursorMode::default(),
+                cursor_position: None,
+                key_repeats: HashMap::new(),
+                gamepads: HashMap::new(),
+                windowed: (x, y, width, height),
+                event_callback: None,
+            },
+            events,
+        };
+
+        if props.mode != WindowMode::Windowed {
+            glfw_window.set_window_mode(props.mode, props.monitor);
+        }
+
+        glfw_window
+    }
+
+    pub fn get_window(&mut self) -> &glfw::Window {
+        &self.window
+    }
+
+    // TODO: Break into sub-handlers
+    fn handle_window_events(&mut self, event: glfw::WindowEvent) {
+        match event {
+            glfw::WindowEvent::Size(width, height) => {
+                self.data.width = width as u32;
+                self.data.height = height as u32;
+
+                self.emit(WindowResizeEvent::new(width as u32, height as u32));
+            }
+            glfw::WindowEvent::Pos(x, y) => {
+                self.emit(WindowMovedEvent::new(x, y));
+            }
+            glfw::WindowEvent::Focus(true) => {
+                self.emit(WindowFocusEvent::new());
+            }
+            glfw::WindowEvent::Focus(false) => {
+                self.emit(WindowLostFocusEvent::new());
+            }
+            glfw::WindowEvent::Close => {
+                self.emit(WindowCloseEvent::new());
+            }
+            glfw::WindowEvent::Key(key, scancode, action, modifiers) => {
+                let key_code = translate_key(key);
+                let modifiers = translate_modifiers(modifiers);
+
+                match action {
+                    Action::Press => {
+                        self.data.key_repeats.insert(scancode, 0);
+                        self.emit(
+                            KeyPressedEvent::new(key_code, 0)
+                                .scancode(scancode as u32)
+                                .modifiers(modifiers),
+                        );
+                    }
+                    Action::Release => {
+                        self.data.key_repeats.remove(&scancode);
+                        self.emit(
+                            KeyReleasedEvent::new(key_code)
+                                .scancode(scancode as u32)
+                                .modifiers(modifiers),
+                        );
+                    }
+                    Action::Repeat => {
+                        let repeats = self.data.key_repeats.entry(scancode).or_insert(0);
+                        *repeats += 1;
+                        let repeat_count = *repeats;
+
+                        self.emit(
+                            KeyPressedEvent::new(key_code, repeat_count)
+                                .scancode(scancode as u32)
+                                .modifiers(modifiers),
+                        );
+                    }
+                }
+            }
+            glfw::WindowEvent::Char(character) => {
+                self.emit(KeyTypedEvent::new(character));
+            }
+            glfw::WindowEvent::MouseButton(mouse_button, action, modifiers) => {
+                let button = translate_mouse_button(mouse_button);
+                let modifiers = translate_modifiers(modifiers);
+
+                match action {
+                    Action::Press => {
+                        self.emit(MouseButtonPressedEvent::new(button).modifiers(modifiers));
+                    }
+                    Action::Release => {
+                        self.emit(MouseButtonReleasedEvent::new(button).modifiers(modifiers));
+                    }
+                    _ => {}
+                }
+            }
+            glfw::WindowEvent::Scroll(x_offset, y_offset) => {
+                self.emit(MouseScrolledEvent::new(x_offset, y_offset));
+            }
+            glfw::WindowEvent::CursorPos(x_pos, y_pos) => {
+                let (x_delta, y_delta) = match self.data.cursor_position {
+                    Some((last_x, last_y)) => (x_pos - last_x, y_pos - last_y),
+                    None => (0.0, 0.0),
+                };
+                self.data.cursor_position = Some((x_pos, y_pos));
+
+                self.emit(MouseMovedEvent::with_delta(x_pos, y_pos, x_delta, y_delta));
+            }
+            _ => {}
+        }
+    }
+
+    /// GLFW has no events for joystick input, so the gamepad state is polled
+    /// every update and compared with the previous one. Joysticks without a
+    /// standard gamepad mapping are ignored.
+    fn poll_gamepads(&mut self) {
+        for joystick_id in (0..16).filter_map(glfw::JoystickId::from_i32) {
+            let joystick = self.window.glfw.get_joystick(joystick_id);
+            let gamepad = joystick_id as u32;
+            let state = if joystick.is_gamepad() {
+                joystick.get_gamepad_state()
+            } else {
+                None
+            };
+
+            match (state, self.data.gamepads.get(&gamepad).copied()) {
+                (Some(state), Some(previous)) => {
+                    self.data.gamepads.insert(gamepad, state);
+                    self.emit_gamepad_changes(gamepad, Some(&previous), &state);
+                }
+                (Some(state), None) => {
+                    let name = joystick.get_gamepad_name().unwrap_or_default();
+
+                    log::info!("Gamepad {} connected: {}", gamepad, name);
+
+                    self.data.gamepads.insert(gamepad, state);
+                    self.emit(GamepadConnectedEvent::new(gamepad, name));
+                    self.emit_gamepad_changes(gamepad, None, &state);
+                }
+                (None, Some(_)) => {
+                    log::info!("Gamepad {} disconnected", gamepad);
+
+                    self.data.gamepads.remove(&gamepad);
+                    self.emit(GamepadDisconnectedEvent::new(gamepad));
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Reports what differs between two polls of `gamepad`. Without a
+    /// previous poll, everything not at rest is reported.
+    fn emit_gamepad_changes(
+        &mut self,
+        gamepad: u32,
+        previous: Option<&glfw::GamepadState>,
+        state: &glfw::GamepadState,
+    ) {
+        for (glfw_button, button) in GAMEPAD_BUTTONS.iter().copied() {
+            let action = state.get_button_state(glfw_button);
+            let previous_action = previous
+                .map(|previous| previous.get_button_state(glfw_button))
+                .unwrap_or(Action::Release);
+
+            if action == previous_action {
+                continue;
+            }
+
+            if action == Action::Release {
+                self.emit(GamepadButtonReleasedEvent::new(gamepad, button));
+            } else {
+                self.emit(GamepadButtonPressedEvent::new(gamepad, button));
+            }
+        }
+
+        for (glfw_axis, axis) in GAMEPAD_AXES.iter().copied() {
+            let value = state.get_axis(glfw_axis);
+            let previous_value = previous
+                .map(|previous| previous.get_axis(glfw_axis))
+                .unwrap_or_else(|| axis.get_rest_value());
+
+            if value != previous_value {
+                self.emit(GamepadAxisMovedEvent::new(gamepad, axis, value));
+            }
+        }
+    }
+
+    fn emit<E: Event>(&mut self, event: E) {
+        match &mut self.data.event_callback {
+            Some(callback) => callback(Box::new(event)),
+            None => log::trace!("{}", event),
+        }
+    }
+}
+
+impl From<glfw::VidMode> for VideoMode {
+    fn from(video_mode: glfw::VidMode) -> Self {
+        VideoMode::new(video_mode.width, video_mode.height, video_mode.refresh_rate)
+    }
+}
+
+fn translate_key(key: glfw::Key) -> KeyCode {
+    // Both enums follow the GLFW key list, so the names match one to one.
+    macro_rules! translate {
+        ($($name:ident),*) => {
+            match key {
+                $(glfw::Key::$name => KeyCode::$name,)*
+            }
+        };
+    }
+
+    translate!(
+        Space,
+        Apostrophe,
+        Comma,
+        Minus,
+        Period,
+        Slash,
+        Num0,
+        Num1,
+        Num2,
+        Num3,
+        Num4,
+        Num5,
+        Num6,
+        Num7,
+        Num8,
+        Num9,
+        Semicolon,
+        Equal,
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        G,
+        H,
+        I,
+        J,
+        K,
+        L,
+        M,
+        N,
+        O,
+        P,
+        Q,
+        R,
+        S,
+        T,
+        U,
+        V,
+        W,
+        X,
+        Y,
+        Z,
+        LeftBracket,
+        Backslash,
+        RightBracket,
+        GraveAccent,
+        World1,
+        World2,
+        Escape,
+        Enter,
+        Tab,
+        Backspace,
+        Insert,
+        Delete,
+        Right,
+        Left,
+        Down,
+        Up,
+        PageUp,
+        PageDown,
+        Home,
+        End,
+        CapsLock,
+        ScrollLock,
+        NumLock,
+        PrintScreen,
+        Pause,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        F25,
+        Kp0,
+        Kp1,
+        Kp2,
+        Kp3,
+        Kp4,
+        Kp5,
+        Kp6,
+        Kp7,
+        Kp8,
+        Kp9,
+        KpDecimal,
+        KpDivide,
+        KpMultiply,
+        KpSubtract,
+        KpAdd,
+        KpEnter,
+        KpEqual,
+        LeftShift,
+        LeftControl,
+        LeftAlt,
+        LeftSuper,
+        RightShift,
+        RightControl,
+        RightAlt,
+        RightSuper,
+        Menu,
+        Unknown
+    )
+}
+
+const GAMEPAD_BUTTONS: [(glfw::GamepadButton, GamepadButton); 15] = [
+    (glfw::GamepadButton::ButtonA, GamepadButton::A),
+    (glfw::GamepadButton::ButtonB, GamepadButton::B),
+    (glfw::GamepadButton::ButtonX, GamepadButton::X),
+    (glfw::GamepadButton::ButtonY, GamepadButton::Y),
+    (
+        glfw::GamepadButton::ButtonLeftBumper,
+        GamepadButton::LeftBumper,
+    ),
+    (
+        glfw::GamepadButton::ButtonRightBumper,
+        GamepadButton::RightBumper,
+    ),
+    (glfw::GamepadButton::ButtonBack, GamepadButton::Back),
+    (glfw::GamepadButton::ButtonStart, GamepadButton::Start),
+    (glfw::GamepadButton::ButtonGuide, GamepadButton::Guide),
+    (
+        glfw::GamepadButton::ButtonLeftThumb,
+        GamepadButton::LeftThumb,
+    ),
+    (
+        glfw::GamepadButton::ButtonRightThumb,
+        GamepadButton::RightThumb,
+    ),
+    (glfw::GamepadButton::ButtonDpadUp, GamepadButton::DpadUp),
+    (
+        glfw::GamepadButton::ButtonDpadRight,
+        GamepadButton::DpadRight,
+    ),
+    (glfw::GamepadButton::ButtonDpadDown, GamepadButton::DpadDown),
+    (glfw::GamepadButton::ButtonDpadLeft, GamepadButton::DpadLeft),
+];
+
+const GAMEPAD_AXES: [(glfw::GamepadAxis, GamepadAxis); 6] = [
+    (glfw::GamepadAxis::AxisLeftX, GamepadAxis::LeftX),
+    (glfw::GamepadAxis::AxisLeftY, GamepadAxis::LeftY),
+    (glfw::GamepadAxis::AxisRightX, GamepadAxis::RightX),
+    (glfw::GamepadAxis::AxisRightY, GamepadAxis::RightY),
+    (glfw::GamepadAxis::AxisLeftTrigger, GamepadAxis::LeftTrigger),
+    (
+        glfw::GamepadAxis::AxisRightTrigger,
+        GamepadAxis::RightTrigger,
+    ),
+];
+
+fn translate_mouse_button(button: glfw::MouseButton) -> MouseButton {
+    match button {
+        glfw::MouseButton::Button1 => MouseButton::Left,
+        glfw::MouseButton::Button2 => MouseButton::Right,
+        glfw::MouseButton::Button3 => MouseButton::Middle,
+        glfw::MouseButton::Button4 => MouseButton::Back,
+        glfw::MouseButton::Button5 => MouseButton::Forward,
+        other => MouseButton::Other(other as u8),
+    }
+}
+
+fn translate_modifiers(modifiers: glfw::Modifiers) -> Modifiers {
+    let mut translated = Modifiers::empty();
+
+    translated.set(Modifiers::SHIFT, modifiers.contains(glfw::Modifiers::Shift));
+    translated.set(
+        Modifiers::CONTROL,
+        modifiers.contains(glfw::Modifiers::Control),
+    );
+    translated.set(Modifiers::ALT, modifiers.contains(glfw::Modifiers::Alt));
+    translated.set(Modifiers::SUPER, modifiers.contains(glfw::Modifiers::Super));
+    translated.set(
+        Modifiers::CAPS_LOCK,
+        modifiers.contains(glfw::Modifiers::CapsLock),
+    );
+    translated.set(
+        Modifiers::NUM_LOCK,
+        modifiers.contains(glfw::Modifiers::NumLock),
+    );
+
+    translated
+}
+
+fn pixel_image(image: &WindowIcon) -> glfw::PixelImage {
+    glfw::PixelImage {
+        width: image.get_width(),
+        height: image.get_height(),
+        pixels: image
+            .get_pixels()
+            .chunks_exact(4)
+            .map(|rgba| u32::from_ne_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+            .collect(),
+    }
+}
+
+fn monitor_info(monitor: &glfw::Monitor) -> MonitorInfo {
+    let (x, y) = monitor.get_pos();
+
+    MonitorInfo {
+        name: monitor.get_name().unwrap_or_default(),
+        x,
+        y,
+        video_mode: monitor
+            .get_video_mode()
+            .map(VideoMode::from)
+            .unwrap_or_else(|| VideoMode::new(0, 0, 0)),
+        video_modes: monitor
+            .get_video_modes()
+            .into_iter()
+            .map(VideoMode::from)
+            .collect(),
+    }
+}
+
+fn error_callback(_: glfw::Error, description: String, error_count: &Cell<usize>) {
+    log::error!("GLFW error {}: {}", error_count.get(), description);
+    error_count.set(error_count.get() + 1);
+}
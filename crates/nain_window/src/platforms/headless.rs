@@ -0,0 +1,602 @@
+use crate::{
+    Cursor, CursorMode, EventCallback, MonitorInfo, VideoMode, Window, WindowMode, WindowProps,
+};
+use nain_events::{
+    AsEvent, Event, GamepadAxis, GamepadAxisMovedEvent, GamepadButton, GamepadButtonPressedEvent,
+    GamepadButtonReleasedEvent, GamepadConnectedEvent, GamepadDisconnectedEvent, KeyTypedEvent,
+    TextInputEvent, WindowMovedEvent, WindowResizeEvent,
+};
+use nain_log as log;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+type ScriptedEvent = (u64, Box<dyn Event + Send>);
+
+struct WindowData {
+    title: String,
+    width: u32,
+    height: u32,
+    position: (i32, i32),
+    min_size: Option<(u32, u32)>,
+    max_size: Option<(u32, u32)>,
+    vsync: bool,
+    mode: WindowMode,
+    monitor: usize,
+    /// Size to restore when leaving fullscreen.
+    windowed: (u32, u32),
+    cursor_mode: CursorMode,
+    cursor: Cursor,
+    event_callback: Option<EventCallback>,
+}
+
+/// Window without a display, for tests and machines without a GPU. Input is
+/// scripted through a `HeadlessInput` and delivered on `on_update`.
+pub struct HeadlessWindow {
+    data: WindowData,
+    frame: u64,
+    input: HeadlessInput,
+    monitors: Vec<MonitorInfo>,
+}
+
+/// Queues events for a `HeadlessWindow`. Clones share the same queue, so a test
+/// can keep one while the application owns the window.
+#[derive(Clone, Default)]
+pub struct HeadlessInput {
+    events: Arc<Mutex<Vec<ScriptedEvent>>>,
+    next_gamepad: Arc<AtomicU32>,
+}
+
+/// Gamepad plugged into a `HeadlessInput`. Every call queues the matching
+/// event for the next update.
+pub struct VirtualGamepad {
+    input: HeadlessInput,
+    gamepad: u32,
+}
+
+impl HeadlessInput {
+    /// Delivers `event` on the next update.
+    pub fn push<E: Event + Send>(&self, event: E) {
+        self.push_at(0, event);
+    }
+
+    /// Delivers `event` on the update that brings the window to `frame`, or on
+    /// the next one if that frame has already passed.
+    pub fn push_at<E: Event + Send>(&self, frame: u64, event: E) {
+        self.events
+            .lock()
+            .expect("Failed to lock headless input")
+            .push((frame, Box::new(event)));
+    }
+
+    /// Types `text` on the next update, like a keyboard would: a
+    /// `KeyTypedEvent` per character, then a `TextInputEvent` with all of it.
+    pub fn type_text<T: Into<String>>(&self, text: T) {
+        let text = text.into();
+
+        for character in text.chars() {
+            self.push(KeyTypedEvent::new(character));
+        }
+
+        self.push(TextInputEvent::new(text));
+    }
+
+    /// Connects a new gamepad. Gamepads are numbered in connection order, and
+    /// numbers are not reused.
+    pub fn connect_gamepad<T: Into<String>>(&self, name: T) -> VirtualGamepad {
+        let gamepad = self.next_gamepad.fetch_add(1, Ordering::SeqCst);
+
+        self.push(GamepadConnectedEvent::new(gamepad, name));
+
+        VirtualGamepad {
+            input: self.clone(),
+            gamepad,
+        }
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.events
+            .lock()
+            .expect("Failed to lock headless input")
+            .len()
+    }
+
+    fn take_due(&self, frame: u64) -> Vec<Box<dyn Event + Send>> {
+        let mut events = self.events.lock().expect("Failed to lock headless input");
+        let mut due = vec![];
+        let mut index = 0;
+
+        // Drain in insertion order so events for the same frame keep their order.
+        while index < events.len() {
+            if events[index].0 <= frame {
+                due.push(events.remove(index).1);
+            } else {
+                index += 1;
+            }
+        }
+
+        due
+    }
+}
+
+impl VirtualGamepad {
+    pub fn get_gamepad(&self) -> u32 {
+        self.gamepad
+    }
+
+    pub fn press(&self, button: GamepadButton) {
+        self.input
+            .push(GamepadButtonPressedEvent::new(self.gamepad, button));
+    }
+
+    pub fn release(&self, button: GamepadButton) {
+        self.input
+            .push(GamepadButtonReleasedEvent::new(self.gamepad, button));
+    }
+
+    pub fn set_axis(&self, axis: GamepadAxis, value: f32) {
+        self.input
+            .push(GamepadAxisMovedEvent::new(self.gamepad, axis, value));
+    }
+
+    pub fn disconnect(self) {
+        self.input.push(GamepadDisconnectedEvent::new(self.gamepad));
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn get_width(&self) -> u32 {
+        self.data.width
+    }
+
+    fn get_height(&self) -> u32 {
+        self.data.height
+    }
+
+    fn get_title(&self) -> &str {
+        &self.data.title
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.data.title = title.to_string();
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) {
+        let (width, height) = self.clamp_size(width, height);
+
+        if (width, height) != (self.data.width, self.data.height) {
+            self.emit(Box::new(WindowResizeEvent::new(width, height)));
+        }
+    }
+
+    fn get_position(&self) -> (i32, i32) {
+        self.data.position
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        if (x, y) != self.data.position {
+            self.emit(Box::new(WindowMovedEvent::new(x, y)));
+        }
+    }
+
+    fn set_vsync(&mut self, enabled: bool) {
+        self.data.vsync = enabled;
+    }
+
+    fn is_vsync_enabled(&self) -> bool {
+        self.data.vsync
+    }
+
+    fn set_event_callback(&mut self, callback: EventCallback) {
+        self.data.event_callback = Some(callback);
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.data.cursor_mode = mode;
+    }
+
+    fn get_cursor_mode(&self) -> CursorMode {
+        self.data.cursor_mode
+    }
+
+    fn set_cursor(&mut self, cursor: Cursor) {
+        self.data.cursor = cursor;
+    }
+
+    fn get_monitors(&self) -> Vec<MonitorInfo> {
+        self.monitors.clone()
+    }
+
+    fn set_window_mode(&mut self, mode: WindowMode, monitor: usize) {
+        if self.data.mode == WindowMode::Windowed {
+            self.data.windowed = (self.data.width, self.data.height);
+        }
+
+        let index = if monitor < self.monitors.len() {
+            monitor
+        } else {
+            log::warn!(
+                "Monitor {} is not connected, using the primary one",
+                monitor
+            );
+            0
+        };
+
+        let (width, height) = match (mode, self.monitors.get(index)) {
+            (WindowMode::Windowed, _) => self.data.windowed,
+            (WindowMode::BorderlessFullscreen, Some(target)) => {
+                (target.video_mode.width, target.video_mode.height)
+            }
+            (WindowMode::Fullscreen(video_mode), Some(target)) => {
+                let video_mode = video_mode.unwrap_or(target.video_mode);
+                (video_mode.width, video_mode.height)
+            }
+            (_, None) => {
+                log::error!("No monitor available for {:?}", mode);
+                return;
+            }
+        };
+
+        self.data.mode = mode;
+        self.data.monitor = index;
+
+        if (width, height) != (self.data.width, self.data.height) {
+            self.emit(Box::new(WindowResizeEvent::new(width, height)));
+        }
+    }
+
+    fn get_window_mode(&self) -> WindowMode {
+        self.data.mode
+    }
+
+    fn get_monitor(&self) -> usize {
+        self.data.monitor
+    }
+
+    fn on_update(&mut self) {
+        self.frame += 1;
+
+        for event in self.input.take_due(self.frame) {
+            self.emit(event);
+        }
+    }
+}
+
+impl HeadlessWindow {
+    pub fn new(props: WindowProps) -> HeadlessWindow {
+        Self::with_input(props, HeadlessInput::default())
+    }
+
+    /// Creates a window reading from an existing `input`, so the caller can
+    /// keep scripting it after handing the window off.
+    pub fn with_input(props: WindowProps, input: HeadlessInput) -> HeadlessWindow {
+        log::info!(
+            "Creating headless window: {} ({}, {})",
+            props.title,
+            props.width,
+            props.height
+        );
+
+        let mut window = HeadlessWindow {
+            data: WindowData {
+                title: props.title,
+                width: props.width,
+                height: props.height,
+                position: props.position.unwrap_or((0, 0)),
+                min_size: props.min_size,
+                max_size: props.max_size,
+                vsync: true,
+                mode: WindowMode::Windowed,
+                monitor: props.monitor,
+                windowed: (props.width, props.height),
+                cursor_mode: CursorMode::default(),
+                cursor: Cursor::default(),
+                event_callback: None,
+            },
+            frame: 0,
+            input,
+            monitors: vec![MonitorInfo {
+                name: "Headless".to_string(),
+                x: 0,
+                y: 0,
+                video_mode: VideoMode::new(1920, 1080, 60),
+                video_modes: vec![
+                    VideoMode::new(1280, 720, 60),
+                    VideoMode::new(1920, 1080, 60),
+                ],
+            }],
+        };
+
+        if props.mode != WindowMode::Windowed {
+            window.set_window_mode(props.mode, props.monitor);
+        }
+
+        window
+    }
+
+    /// Number of updates so far.
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn input(&self) -> HeadlessInput {
+        self.input.clone()
+    }
+
+    pub fn get_cursor(&self) -> &Cursor {
+        &self.data.cursor
+    }
+
+    /// Replaces the simulated monitors. The first one acts as the primary.
+    pub fn set_monitors(&mut self, monitors: Vec<MonitorInfo>) {
+        self.monitors = monitors;
+    }
+
+    fn clamp_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (mut width, mut height) = (width, height);
+
+        if let Some((min_width, min_height)) = self.data.min_size {
+            width = width.max(min_width);
+            height = height.max(min_height);
+        }
+
+        if let Some((max_width, max_height)) = self.data.max_size {
+            width = width.min(max_width);
+            height = height.min(max_height);
+        }
+
+        (width, height)
+    }
+
+    fn emit(&mut self, mut event: Box<dyn Event>) {
+        if let Some(resize) = WindowResizeEvent::from_event_mut(event.as_mut()) {
+            self.data.width = resize.get_width();
+            self.data.height = resize.get_height();
+        }
+
+        if let Some(moved) = WindowMovedEvent::from_event_mut(event.as_mut()) {
+            self.data.position = (moved.get_x(), moved.get_y());
+        }
+
+        match &mut self.data.event_callback {
+            Some(callback) => callback(event),
+            None => log::trace!("{}", event),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WindowIcon, WindowIconError};
+    use nain_events::{KeyCode, KeyPressedEvent, WindowCloseEvent};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn window_with_log() -> (HeadlessWindow, Rc<RefCell<Vec<String>>>) {
+        let mut window = HeadlessWindow::new(WindowProps::new("Headless", 320, 240));
+        let log = Rc::new(RefCell::new(vec![]));
+        let callback_log = log.clone();
+
+        window.set_event_callback(Box::new(move |event| {
+            callback_log.borrow_mut().push(event.to_string());
+        }));
+
+        (window, log)
+    }
+
+    #[test]
+    fn uses_the_configured_size() {
+        let mut window = HeadlessWindow::new(WindowProps::new("Headless", 320, 240));
+        window.set_vsync(false);
+
+        assert_eq!(window.get_width(), 320);
+        assert_eq!(window.get_height(), 240);
+        assert_eq!(window.get_title(), "Headless");
+        assert!(!window.is_vsync_enabled());
+    }
+
+    #[test]
+    fn delivers_pushed_events_on_the_next_update() {
+        let (mut window, log) = window_with_log();
+        let input = window.input();
+
+        input.push(KeyPressedEvent::new(KeyCode::A, 0));
+        input.push(WindowCloseEvent::new());
+
+        assert!(log.borrow().is_empty());
+        window.on_update();
+        assert_eq!(
+            *log.borrow(),
+            vec!["KeyPressedEvent: A (0 repeats)", "WindowCloseEvent"]
+        );
+        assert_eq!(input.pending_count(), 0);
+    }
+
+    #[test]
+    fn delivers_scheduled_events_on_their_frame() {
+        let (mut window, log) = window_with_log();
+        let input = window.input();
+
+        input.push_at(3, WindowCloseEvent::new());
+
+        window.on_update();
+        window.on_update();
+        assert!(log.borrow().is_empty());
+
+        window.on_update();
+        assert_eq!(window.get_frame(), 3);
+        assert_eq!(*log.borrow(), vec!["WindowCloseEvent"]);
+    }
+
+    #[test]
+    fn types_text() {
+        let (mut window, log) = window_with_log();
+
+        window.input().type_text("né");
+        window.on_update();
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "KeyTypedEvent: 'n'",
+                "KeyTypedEvent: 'é'",
+                "TextInputEvent: \"né\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn virtual_gamepads_queue_their_events() {
+        let (mut window, log) = window_with_log();
+        let input = window.input();
+
+        let first = input.connect_gamepad("First");
+        let second = input.connect_gamepad("Second");
+        second.press(GamepadButton::A);
+        second.set_axis(GamepadAxis::LeftX, -0.5);
+        second.release(GamepadButton::A);
+        first.disconnect();
+        window.on_update();
+
+        assert_eq!(second.get_gamepad(), 1);
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "GamepadConnectedEvent: 0 (First)",
+                "GamepadConnectedEvent: 1 (Second)",
+                "GamepadButtonPressedEvent: 1 - A",
+                "GamepadAxisMovedEvent: 1 - LeftX: -0.5",
+                "GamepadButtonReleasedEvent: 1 - A",
+                "GamepadDisconnectedEvent: 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn resize_events_update_the_size() {
+        let (mut window, _) = window_with_log();
+
+        window.input().push(WindowResizeEvent::new(800, 600));
+        window.on_update();
+
+        assert_eq!(window.get_width(), 800);
+        assert_eq!(window.get_height(), 600);
+    }
+
+    #[test]
+    fn switches_between_windowed_and_fullscreen() {
+        let (mut window, log) = window_with_log();
+
+        window.toggle_fullscreen();
+        assert_eq!(window.get_window_mode(), WindowMode::BorderlessFullscreen);
+        assert_eq!((window.get_width(), window.get_height()), (1920, 1080));
+
+        window.set_window_mode(
+            WindowMode::Fullscreen(Some(VideoMode::new(1280, 720, 60))),
+            0,
+        );
+        assert_eq!((window.get_width(), window.get_height()), (1280, 720));
+
+        window.toggle_fullscreen();
+        assert_eq!(window.get_window_mode(), WindowMode::Windowed);
+        assert_eq!((window.get_width(), window.get_height()), (320, 240));
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "WindowResizeEvent: 1920 - 1080",
+                "WindowResizeEvent: 1280 - 720",
+                "WindowResizeEvent: 320 - 240",
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_primary_monitor() {
+        let (mut window, _) = window_with_log();
+        let mut second = window.get_monitors()[0].clone();
+        second.name = "Second".to_string();
+        second.video_mode = VideoMode::new(2560, 1440, 144);
+        window.set_monitors(vec![window.get_monitors()[0].clone(), second]);
+
+        window.set_window_mode(WindowMode::BorderlessFullscreen, 1);
+        assert_eq!(window.get_monitor(), 1);
+        assert_eq!(window.get_width(), 2560);
+
+        window.set_window_mode(WindowMode::BorderlessFullscreen, 7);
+        assert_eq!(window.get_monitor(), 0);
+        assert_eq!(window.get_width(), 1920);
+    }
+
+    #[test]
+    fn applies_title_size_and_position_changes() {
+        let props = WindowProps::new(String::from("Config"), 320, 240)
+            .position(10, 20)
+            .min_size(200, 100)
+            .max_size(640, 480);
+        let mut window = HeadlessWindow::new(props);
+        let log = Rc::new(RefCell::new(vec![]));
+        let callback_log = log.clone();
+
+        window.set_event_callback(Box::new(move |event| {
+            callback_log.borrow_mut().push(event.to_string());
+        }));
+
+        window.set_title("Renamed");
+        window.set_size(1000, 50);
+        window.set_position(30, 40);
+
+        assert_eq!(window.get_title(), "Renamed");
+        assert_eq!((window.get_width(), window.get_height()), (640, 100));
+        assert_eq!(window.get_position(), (30, 40));
+        assert_eq!(
+            *log.borrow(),
+            vec!["WindowResizeEvent: 640 - 100", "WindowMovedEvent: 30, 40"]
+        );
+    }
+
+    #[test]
+    fn checks_icon_sizes() {
+        let icon = WindowIcon::new(2, 1, vec![255; 8]).unwrap();
+        assert_eq!((icon.get_width(), icon.get_height()), (2, 1));
+        assert_eq!(icon.get_pixels().len(), 8);
+
+        assert_eq!(
+            WindowIcon::new(2, 1, vec![255; 4]),
+            Err(WindowIconError::PixelCountMismatch {
+                width: 2,
+                height: 1,
+                len: 4
+            })
+        );
+        assert_eq!(
+            WindowIcon::new(u32::MAX, u32::MAX, vec![]),
+            Err(WindowIconError::TooLarge {
+                width: u32::MAX,
+                height: u32::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_the_cursor_state() {
+        let mut window = HeadlessWindow::new(WindowProps::default());
+        let image = WindowIcon::new(1, 1, vec![255; 4]).unwrap();
+
+        assert_eq!(window.get_cursor_mode(), CursorMode::Normal);
+
+        window.set_cursor_mode(CursorMode::Disabled);
+        window.set_cursor(Cursor::Custom {
+            image: image.clone(),
+            hotspot: (0, 0),
+        });
+
+        assert_eq!(window.get_cursor_mode(), CursorMode::Disabled);
+        assert_eq!(
+            *window.get_cursor(),
+            Cursor::Custom {
+                image,
+                hotspot: (0, 0)
+            }
+        );
+    }
+}
@@ -1,3 +1,7 @@
-pub use self::windows_window::WindowsWindow;
+#[cfg(feature = "glfw")]
+pub use self::glfw::GlfwWindow;
+pub use self::headless::{HeadlessInput, HeadlessWindow, VirtualGamepad};
 
-mod windows_window;
\ No newline at end of file
+#[cfg(feature = "glfw")]
+mod glfw;
+mod headless;
@@ -1,34 +1,215 @@
+use crate::{Cursor, CursorMode, MonitorInfo, VideoMode};
+use nain_events::Event;
+use std::error::Error;
+use std::fmt;
+
+/// Receives every event translated from the native window.
+pub type EventCallback = Box<dyn FnMut(Box<dyn Event>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    /// Undecorated window covering the monitor at its desktop resolution.
+    BorderlessFullscreen,
+    /// Exclusive fullscreen. `None` keeps the monitor's current video mode.
+    Fullscreen(Option<VideoMode>),
+}
+
+/// RGBA image shown by the platform for the window, e.g. in the task bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowIcon {
+    width: u32,
+    height: u32,
+    /// Rows of RGBA pixels, 4 bytes each, top to bottom.
+    pixels: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WindowIconError {
+    TooLarge { width: u32, height: u32 },
+    PixelCountMismatch { width: u32, height: u32, len: usize },
+}
+
+impl fmt::Display for WindowIconError {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowIconError::TooLarge { width, height } => {
+                write!(formater, "Icon is too large: {}x{}", width, height)
+            }
+            WindowIconError::PixelCountMismatch { width, height, len } => write!(
+                formater,
+                "Icon pixels do not match a {}x{} RGBA image: {} bytes",
+                width, height, len
+            ),
+        }
+    }
+}
+
+impl Error for WindowIconError {}
+
+impl WindowIcon {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, WindowIconError> {
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or(WindowIconError::TooLarge { width, height })?;
+
+        if pixels.len() != expected {
+            return Err(WindowIconError::PixelCountMismatch {
+                width,
+                height,
+                len: pixels.len(),
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Rows of RGBA pixels, 4 bytes each, top to bottom.
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct WindowProps {
-    pub title: &'static str,
+    pub title: String,
     pub width: u32,
     pub height: u32,
+    pub mode: WindowMode,
+    /// Index into `Window::get_monitors` used by the fullscreen modes.
+    pub monitor: usize,
+    pub resizable: bool,
+    pub decorated: bool,
+    /// Lets the framebuffer alpha show through to the desktop.
+    pub transparent: bool,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    /// Initial position in screen coordinates, `None` leaves it to the platform.
+    pub position: Option<(i32, i32)>,
+    pub icon: Option<WindowIcon>,
 }
 
 impl Default for WindowProps {
     fn default() -> Self {
         Self {
-            title: "Nain Engine",
+            title: "Nain Engine".to_string(),
             width: 1280,
             height: 720,
+            mode: WindowMode::Windowed,
+            monitor: 0,
+            resizable: true,
+            decorated: true,
+            transparent: false,
+            min_size: None,
+            max_size: None,
+            position: None,
+            icon: None,
         }
     }
 }
 
 impl WindowProps {
-    pub fn new(title: &'static str, width: u32, height: u32) -> Self {
+    pub fn new<T: Into<String>>(title: T, width: u32, height: u32) -> Self {
         Self {
-            title,
+            title: title.into(),
             width,
             height,
+            ..Self::default()
         }
     }
+
+    pub fn mode(mut self, mode: WindowMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn monitor(mut self, monitor: usize) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn decorated(mut self, decorated: bool) -> Self {
+        self.decorated = decorated;
+        self
+    }
+
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn icon(mut self, icon: WindowIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
 }
 
 pub trait Window {
     fn on_update(&mut self);
     fn get_width(&self) -> u32;
     fn get_height(&self) -> u32;
+    fn get_title(&self) -> &str;
+    fn set_title(&mut self, title: &str);
+    /// Resizes the client area. Sizes outside the configured limits are
+    /// clamped.
+    fn set_size(&mut self, width: u32, height: u32);
+    fn get_position(&self) -> (i32, i32);
+    fn set_position(&mut self, x: i32, y: i32);
     fn set_vsync(&mut self, enabled: bool);
     fn is_vsync_enabled(&self) -> bool;
-    fn create(props: WindowProps) -> Self;
+    fn set_event_callback(&mut self, callback: EventCallback);
+    fn set_cursor_mode(&mut self, mode: CursorMode);
+    fn get_cursor_mode(&self) -> CursorMode;
+    fn set_cursor(&mut self, cursor: Cursor);
+    fn get_monitors(&self) -> Vec<MonitorInfo>;
+    /// Switches to `mode` on the monitor at `monitor`, falling back to the
+    /// primary monitor if it is not connected. Going back to `Windowed`
+    /// restores the size and position the window had before.
+    fn set_window_mode(&mut self, mode: WindowMode, monitor: usize);
+    fn get_window_mode(&self) -> WindowMode;
+    fn get_monitor(&self) -> usize;
+
+    /// Switches between windowed and borderless fullscreen on the current
+    /// monitor.
+    fn toggle_fullscreen(&mut self) {
+        let mode = match self.get_window_mode() {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+
+        self.set_window_mode(mode, self.get_monitor());
+    }
 }
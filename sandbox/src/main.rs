@@ -1,10 +1,6 @@
-use nain::{Application, CreateApplication};
-use std::error::Error;
+#[derive(Default)]
+struct Sandbox;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut sandbox = Application::new();
+impl nain::Game for Sandbox {}
 
-    CreateApplication::new(&mut sandbox)?;
-
-    Ok(())
-}
+nain::main!(Sandbox);
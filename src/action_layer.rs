@@ -0,0 +1,451 @@
+use crate::events::{
+    enqueue_event, ActionEvent, AsEvent, Event, GamepadAxis, GamepadAxisMovedEvent,
+    GamepadButtonPressedEvent, GamepadButtonReleasedEvent, GamepadDisconnectedEvent, GamepadEvent,
+    KeyEvent, KeyPressedEvent, KeyReleasedEvent, MouseButtonEvent, MouseButtonPressedEvent,
+    MouseButtonReleasedEvent, WindowLostFocusEvent,
+};
+use crate::input_map::{AxisBinding, InputMap, InputSource};
+use crate::layer::Layer;
+use crate::log;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Turns the key, mouse button and gamepad events reaching the layer stack into
+/// `ActionEvent`s queued on a bus, according to an `InputMap`. Clones share
+/// the same state, so the game can keep one to rebind while the stack owns
+/// another.
+#[derive(Clone)]
+pub struct ActionLayer {
+    state: Arc<Mutex<ActionState>>,
+}
+
+struct ActionState {
+    bus: String,
+    bindings: InputMap,
+    /// Inputs held down, with the gamepad they are on if any.
+    held: HashSet<(Option<u32>, InputSource)>,
+    gamepad_axes: HashMap<(u32, GamepadAxis), f32>,
+    /// Last reported value of every action and axis that is not at zero.
+    values: BTreeMap<String, f32>,
+    rebinding: Option<String>,
+}
+
+impl ActionLayer {
+    pub fn new<T: Into<String>>(bus: T, bindings: InputMap) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ActionState {
+                bus: bus.into(),
+                bindings,
+                held: HashSet::new(),
+                gamepad_axes: HashMap::new(),
+                values: BTreeMap::new(),
+                rebinding: None,
+            })),
+        }
+    }
+
+    pub fn get_bindings(&self) -> InputMap {
+        self.lock().bindings.clone()
+    }
+
+    pub fn set_bindings(&self, bindings: InputMap) {
+        self.update_bindings(|current| *current = bindings);
+    }
+
+    /// Edits the bindings in place. Actions whose value changes because of
+    /// it are reported like any other change.
+    pub fn update_bindings<F: FnOnce(&mut InputMap)>(&self, edit: F) {
+        let mut state = self.lock();
+
+        edit(&mut state.bindings);
+        state.evaluate();
+    }
+
+    /// Binds the next key or button pressed to `action`, replacing its
+    /// other bindings. That press triggers nothing else.
+    pub fn rebind_next(&self, action: &str) {
+        self.lock().rebinding = Some(action.to_string());
+    }
+
+    pub fn is_rebinding(&self) -> bool {
+        self.lock().rebinding.is_some()
+    }
+
+    pub fn get_value(&self, action: &str) -> f32 {
+        self.lock().values.get(action).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.get_value(action) != 0.0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ActionState> {
+        self.state.lock().expect("Failed to lock action state")
+    }
+}
+
+impl Layer for ActionLayer {
+    fn get_name(&self) -> &str {
+        "ActionLayer"
+    }
+
+    fn on_event(&mut self, event: &mut dyn Event) {
+        let mut state = self.lock();
+
+        if WindowLostFocusEvent::from_event_mut(event).is_some() {
+            // Gamepads are still polled without focus, so their releases
+            // keep arriving.
+            state.held.retain(|(gamepad, _)| gamepad.is_some());
+            state.evaluate();
+            return;
+        }
+
+        if let Some(event) = GamepadDisconnectedEvent::from_event_mut(event) {
+            let disconnected = event.get_gamepad();
+
+            state
+                .held
+                .retain(|(gamepad, _)| *gamepad != Some(disconnected));
+            state
+                .gamepad_axes
+                .retain(|(gamepad, _), _| *gamepad != disconnected);
+            state.evaluate();
+            return;
+        }
+
+        if let Some(event) = GamepadAxisMovedEvent::from_event_mut(event) {
+            state
+                .gamepad_axes
+                .insert((event.get_gamepad(), event.get_axis()), event.get_value());
+            state.evaluate();
+            return;
+        }
+
+        let (gamepad, source, down) = match input_of(event) {
+            Some(input) => input,
+            None => return,
+        };
+
+        if down {
+            if let Some(action) = state.rebinding.take() {
+                log::info!("Binding {} to {}", action, source);
+
+                state.bindings.rebind_action(&action, &[source]);
+                state.evaluate();
+                event.set_handled(true);
+                return;
+            }
+
+            state.held.insert((gamepad, source));
+        } else {
+            state.held.remove(&(gamepad, source));
+        }
+
+        state.evaluate();
+    }
+}
+
+impl ActionState {
+    /// Recomputes every action and axis, and queues an event for each one
+    /// whose value changed, in name order.
+    fn evaluate(&mut self) {
+        let mut values = BTreeMap::new();
+
+        for (action, chords) in self.bindings.actions() {
+            let active = chords
+                .iter()
+                .any(|chord| !chord.is_empty() && chord.iter().all(|input| self.is_down(*input)));
+
+            if active {
+                values.insert(action.to_string(), 1.0);
+            }
+        }
+
+        for (axis, bindings) in self.bindings.axes() {
+            let value: f32 = bindings
+                .bindings
+                .iter()
+                .map(|binding| self.get_binding_value(binding))
+                .sum::<f32>()
+                .clamp(-1.0, 1.0);
+
+            if value.abs() > bindings.dead_zone {
+                values.insert(axis.to_string(), value);
+            }
+        }
+
+        let mut changes = vec![];
+
+        for (name, value) in values.iter() {
+            if self.values.get(name) != Some(value) {
+                changes.push((name.clone(), *value));
+            }
+        }
+
+        for name in self.values.keys() {
+            if !values.contains_key(name) {
+                changes.push((name.clone(), 0.0));
+            }
+        }
+
+        changes.sort_by(|(first, _), (second, _)| first.cmp(second));
+        self.values = values;
+
+        for (name, value) in changes {
+            if let Err(error) = enqueue_event(&self.bus, ActionEvent::new(name, value)) {
+                log::error!("{}", error);
+            }
+        }
+    }
+
+    fn is_down(&self, input: InputSource) -> bool {
+        self.held.iter().any(|(_, held)| *held == input)
+    }
+
+    fn get_binding_value(&self, binding: &AxisBinding) -> f32 {
+        match binding {
+            AxisBinding::Buttons { negative, positive } => {
+                self.is_held(*positive) - self.is_held(*negative)
+            }
+            AxisBinding::Gamepad { axis, inverted } => {
+                // With several gamepads, the one pushed furthest wins.
+                let value = self
+                    .gamepad_axes
+                    .iter()
+                    .filter(|((_, moved), _)| moved == axis)
+                    .map(|(_, value)| match axis {
+                        GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => (value + 1.0) / 2.0,
+                        _ => *value,
+                    })
+                    .fold(0.0, |furthest: f32, value| {
+                        if value.abs() > furthest.abs() {
+                            value
+                        } else {
+                            furthest
+                        }
+                    });
+
+                if *inverted {
+                    -value
+                } else {
+                    value
+                }
+            }
+        }
+    }
+
+    fn is_held(&self, input: InputSource) -> f32 {
+        if self.is_down(input) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The gamepad `event` comes from if any, the input it is about and whether
+/// it went down.
+fn input_of(event: &mut dyn Event) -> Option<(Option<u32>, InputSource, bool)> {
+    if let Some(event) = KeyPressedEvent::from_event_mut(event) {
+        if event.get_repeat_count() > 0 {
+            return None;
+        }
+
+        Some((None, InputSource::Key(event.get_key_code()), true))
+    } else if let Some(event) = KeyReleasedEvent::from_event_mut(event) {
+        Some((None, InputSource::Key(event.get_key_code()), false))
+    } else if let Some(event) = MouseButtonPressedEvent::from_event_mut(event) {
+        Some((
+            None,
+            InputSource::MouseButton(event.get_mouse_button()),
+            true,
+        ))
+    } else if let Some(event) = MouseButtonReleasedEvent::from_event_mut(event) {
+        Some((
+            None,
+            InputSource::MouseButton(event.get_mouse_button()),
+            false,
+        ))
+    } else if let Some(event) = GamepadButtonPressedEvent::from_event_mut(event) {
+        let source = InputSource::GamepadButton(event.get_button());
+
+        Some((Some(event.get_gamepad()), source, true))
+    } else {
+        GamepadButtonReleasedEvent::from_event_mut(event).map(|event| {
+            let source = InputSource::GamepadButton(event.get_button());
+
+            (Some(event.get_gamepad()), source, false)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{subscribe_event, EventBus, GamepadButton, KeyCode, MouseButton};
+
+    fn record_actions(bus: &EventBus) -> Arc<Mutex<Vec<String>>> {
+        let actions = Arc::new(Mutex::new(vec![]));
+        let handler_actions = actions.clone();
+
+        subscribe_event(
+            bus.get_name(),
+            move |event: &mut ActionEvent| {
+                handler_actions.lock().unwrap().push(event.to_string());
+            },
+            0,
+        )
+        .unwrap();
+
+        actions
+    }
+
+    fn key(key_code: KeyCode) -> InputSource {
+        InputSource::Key(key_code)
+    }
+
+    #[test]
+    fn reports_actions_and_chords() {
+        let bus = EventBus::new("test_action_chords");
+        let actions = record_actions(&bus);
+        let mut bindings = InputMap::new();
+        bindings.bind_action("Jump", &[key(KeyCode::Space)]);
+        bindings.bind_action("Save", &[key(KeyCode::LeftControl), key(KeyCode::S)]);
+        bindings.bind_action("Fire", &[InputSource::MouseButton(MouseButton::Left)]);
+        let mut layer = ActionLayer::new(bus.get_name(), bindings);
+
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::Space, 0));
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::Space, 1));
+        layer.on_event(&mut KeyReleasedEvent::new(KeyCode::Space));
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::S, 0));
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::LeftControl, 0));
+        layer.on_event(&mut MouseButtonPressedEvent::new(MouseButton::Left));
+        bus.flush().unwrap();
+
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![
+                "ActionEvent: Jump (1)",
+                "ActionEvent: Jump (0)",
+                "ActionEvent: Save (1)",
+                "ActionEvent: Fire (1)",
+            ]
+        );
+        assert!(layer.is_pressed("Save"));
+
+        actions.lock().unwrap().clear();
+        layer.on_event(&mut WindowLostFocusEvent::new());
+        bus.flush().unwrap();
+
+        assert!(!layer.is_pressed("Save"));
+        assert!(!layer.is_pressed("Fire"));
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec!["ActionEvent: Fire (0)", "ActionEvent: Save (0)"]
+        );
+    }
+
+    #[test]
+    fn combines_axis_bindings() {
+        let bus = EventBus::new("test_action_axes");
+        let actions = record_actions(&bus);
+        let mut bindings = InputMap::new();
+        bindings.bind_axis("MoveX", key(KeyCode::A), key(KeyCode::D));
+        bindings.bind_axis("MoveX", key(KeyCode::Left), key(KeyCode::Right));
+        let mut layer = ActionLayer::new(bus.get_name(), bindings);
+
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::D, 0));
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::Right, 0));
+        assert_eq!(layer.get_value("MoveX"), 1.0);
+
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::A, 0));
+        assert_eq!(layer.get_value("MoveX"), 1.0);
+
+        layer.on_event(&mut KeyReleasedEvent::new(KeyCode::Right));
+        assert_eq!(layer.get_value("MoveX"), 0.0);
+
+        layer.update_bindings(|bindings| bindings.set_dead_zone("MoveX", 1.0));
+        layer.on_event(&mut KeyReleasedEvent::new(KeyCode::A));
+        bus.flush().unwrap();
+
+        assert_eq!(layer.get_value("MoveX"), 0.0);
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec!["ActionEvent: MoveX (1)", "ActionEvent: MoveX (0)"]
+        );
+    }
+
+    #[test]
+    fn reads_gamepads() {
+        let bus = EventBus::new("test_action_gamepads");
+        let actions = record_actions(&bus);
+        let mut bindings = InputMap::new();
+        bindings.bind_action("Jump", &[InputSource::GamepadButton(GamepadButton::A)]);
+        bindings.bind_gamepad_axis("MoveY", GamepadAxis::LeftY, true);
+        bindings.bind_gamepad_axis("Throttle", GamepadAxis::RightTrigger, false);
+        bindings.set_dead_zone("MoveY", 0.2);
+        let mut layer = ActionLayer::new(bus.get_name(), bindings);
+
+        layer.on_event(&mut GamepadButtonPressedEvent::new(0, GamepadButton::A));
+        layer.on_event(&mut GamepadButtonPressedEvent::new(1, GamepadButton::A));
+        layer.on_event(&mut GamepadButtonReleasedEvent::new(0, GamepadButton::A));
+        assert!(layer.is_pressed("Jump"));
+
+        layer.on_event(&mut GamepadAxisMovedEvent::new(0, GamepadAxis::LeftY, 0.1));
+        assert_eq!(layer.get_value("MoveY"), 0.0);
+
+        layer.on_event(&mut GamepadAxisMovedEvent::new(1, GamepadAxis::LeftY, 0.5));
+        layer.on_event(&mut GamepadAxisMovedEvent::new(
+            1,
+            GamepadAxis::RightTrigger,
+            -1.0,
+        ));
+        assert_eq!(layer.get_value("MoveY"), -0.5);
+        assert_eq!(layer.get_value("Throttle"), 0.0);
+
+        bus.flush().unwrap();
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec!["ActionEvent: Jump (1)", "ActionEvent: MoveY (-0.5)"]
+        );
+
+        layer.on_event(&mut GamepadDisconnectedEvent::new(1));
+        assert!(!layer.is_pressed("Jump"));
+        assert_eq!(layer.get_value("MoveY"), 0.0);
+    }
+
+    #[test]
+    fn rebinds_to_the_next_press() {
+        let bus = EventBus::new("test_action_rebind");
+        let actions = record_actions(&bus);
+        let mut bindings = InputMap::new();
+        bindings.bind_action("Jump", &[key(KeyCode::Space)]);
+        let mut layer = ActionLayer::new(bus.get_name(), bindings);
+
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::Space, 0));
+        layer.rebind_next("Jump");
+        let mut press = KeyPressedEvent::new(KeyCode::J, 0);
+        layer.on_event(&mut press);
+
+        assert!(press.is_handled());
+        assert!(!layer.is_rebinding());
+        assert!(!layer.is_pressed("Jump"));
+        assert_eq!(
+            layer.get_bindings().get_action("Jump"),
+            Some(&[vec![key(KeyCode::J)]][..])
+        );
+
+        layer.on_event(&mut KeyReleasedEvent::new(KeyCode::J));
+        layer.on_event(&mut KeyPressedEvent::new(KeyCode::J, 0));
+        bus.flush().unwrap();
+
+        assert_eq!(
+            *actions.lock().unwrap(),
+            vec![
+                "ActionEvent: Jump (1)",
+                "ActionEvent: Jump (0)",
+                "ActionEvent: Jump (1)",
+            ]
+        );
+    }
+}
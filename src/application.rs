@@ -1,33 +1,452 @@
-use crate::events::{dispatch_event, subscribe_event, Event, EventBus, KeyPressedEvent};
+use crate::events::{dispatch_event, AsEvent, Event, EventBus, WindowCloseEvent};
+use crate::game::Game;
+use crate::input::Input;
+use crate::layer::Layer;
+use crate::layer_stack::LayerStack;
 use crate::log;
-use crate::window::{Window, WindowProps, WindowTrait};
+use crate::time::{Clock, FixedTimestep, FrameStats, FrameTimer, SystemClock, Timestep};
+use crate::window::{Window, WindowBackend, WindowBuilder, WindowProps};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
 
+static APPLICATION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Window events go to the input state, then down the layer stack, then to
+/// the application's event bus. An event marked handled stops there. A
+/// `WindowCloseEvent` exits the main loop unless a layer or a bus handler
+/// handles it, which is how closing is cancelled.
 pub struct Application {
-    window: Window<'static>,
-    is_running: bool,
+    window: Box<dyn Window>,
+    event_bus: EventBus,
+    window_events: Receiver<Box<dyn Event>>,
+    layer_stack: LayerStack,
+    input: Input,
+    frame_timer: FrameTimer,
+    fixed_timestep: FixedTimestep,
+    frame_stats: FrameStats,
+    exit: ExitHandle,
+    shutdown_hooks: Vec<Box<dyn FnOnce()>>,
+}
+
+/// Lets code that does not own the `Application`, like bus handlers or other
+/// threads, ask it to stop after the current frame.
+#[derive(Clone)]
+pub struct ExitHandle {
+    requested: Arc<AtomicBool>,
+    code: Arc<AtomicI32>,
+}
+
+impl ExitHandle {
+    fn new() -> Self {
+        Self {
+            requested: Arc::new(AtomicBool::new(false)),
+            code: Arc::new(AtomicI32::new(0)),
+        }
+    }
+
+    pub fn request_exit(&self, code: i32) {
+        self.code.store(code, Ordering::SeqCst);
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_exit_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn get_exit_code(&self) -> i32 {
+        self.code.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for Application {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Application {
     pub fn new() -> Self {
+        Self::with_window_props(WindowProps::default())
+    }
+
+    pub fn with_window_props(props: WindowProps) -> Self {
+        Self::with_window(
+            WindowBuilder::new(props)
+                .backend(WindowBackend::from_env())
+                .build(),
+        )
+    }
+
+    pub fn with_window(mut window: Box<dyn Window>) -> Self {
+        let (sender, window_events) = channel();
+
+        window.set_event_callback(Box::new(move |event| {
+            // The receiver only goes away with the application itself.
+            let _ = sender.send(event);
+        }));
+
+        // Bus names are global, so every application gets its own.
+        let id = APPLICATION_COUNT.fetch_add(1, Ordering::Relaxed);
+
         Self {
-            window: Window::create(WindowProps::default()),
-            is_running: true,
+            window,
+            event_bus: EventBus::new(format!("nain_engine_{}", id)),
+            window_events,
+            layer_stack: LayerStack::new(),
+            input: Input::new(),
+            frame_timer: FrameTimer::new(Box::new(SystemClock::default())),
+            fixed_timestep: FixedTimestep::default(),
+            frame_stats: FrameStats::default(),
+            exit: ExitHandle::new(),
+            shutdown_hooks: vec![],
         }
     }
 
-    fn on_event(&self, event: &dyn Event) {
-        log::info!("event: {}", event);
+    pub fn push_layer<L: Layer + 'static>(&mut self, layer: L) {
+        self.layer_stack.push_layer(Box::new(layer));
+    }
+
+    pub fn push_overlay<L: Layer + 'static>(&mut self, overlay: L) {
+        self.layer_stack.push_overlay(Box::new(overlay));
+    }
+
+    pub fn pop_layer(&mut self) -> Option<Box<dyn Layer>> {
+        self.layer_stack.pop_layer()
+    }
+
+    pub fn pop_overlay(&mut self) -> Option<Box<dyn Layer>> {
+        self.layer_stack.pop_overlay()
+    }
+
+    /// Replaces the clock driving frame timing, e.g. with a `ManualClock` in
+    /// tests.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.frame_timer = FrameTimer::new(clock);
     }
 
-    pub fn run(&mut self) {
+    pub fn get_fixed_timestep(&self) -> &FixedTimestep {
+        &self.fixed_timestep
+    }
+
+    pub fn get_fixed_timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.fixed_timestep
+    }
+
+    pub fn get_frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Polled keyboard and mouse state. Clone it to query from a layer.
+    pub fn get_input(&self) -> &Input {
+        &self.input
+    }
+
+    /// Bus the window events are dispatched on, and events queued on it
+    /// flushed at the end of every frame.
+    pub fn get_event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    pub fn get_window(&self) -> &dyn Window {
+        self.window.as_ref()
+    }
+
+    pub fn get_window_mut(&mut self) -> &mut dyn Window {
+        self.window.as_mut()
+    }
+
+    /// Stops the main loop at the end of the current frame. `run` then returns
+    /// `code`.
+    pub fn request_exit(&self, code: i32) {
+        self.exit.request_exit(code);
+    }
+
+    pub fn exit_handle(&self) -> ExitHandle {
+        self.exit.clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.exit.is_exit_requested()
+    }
+
+    /// Registers `hook` to run once the main loop has stopped. Hooks run in
+    /// reverse order of registration.
+    pub fn add_shutdown_hook<F: FnOnce() + 'static>(&mut self, hook: F) {
+        self.shutdown_hooks.push(Box::new(hook));
+    }
+
+    fn on_event(&mut self, event: &mut dyn Event) {
+        log::trace!("{}", event);
+
+        self.input.on_event(event);
+        self.layer_stack.on_event(event);
+
+        let consumed = match dispatch_event(self.event_bus.get_name(), event) {
+            Ok(consumed) => consumed,
+            Err(error) => {
+                log::error!("{}", error);
+                false
+            }
+        };
+
+        if WindowCloseEvent::from_event_mut(event).is_some() {
+            if consumed {
+                log::info!("Window close cancelled");
+            } else {
+                self.request_exit(0);
+            }
+        }
+    }
+
+    pub fn run<G: Game + ?Sized>(&mut self, game: &mut G) -> i32 {
         log::info!("Welcome to the Nain engine!");
 
-        let mut event = KeyPressedEvent::new(1, 0);
+        game.init(self);
+
+        self.frame_timer.reset();
+
+        while self.is_running() {
+            let frame_time = self.frame_timer.tick();
+            let dt = Timestep::from(frame_time);
+
+            self.frame_stats.record(frame_time);
+
+            let steps = self.fixed_timestep.accumulate(frame_time);
+            let step = self.fixed_timestep.get_step();
 
-        dispatch_event("nain_engine", &mut event);
+            for _ in 0..steps {
+                self.layer_stack.on_fixed_update(step);
+                game.fixed_update(self, step);
+            }
 
-        while self.is_running {
+            self.layer_stack.on_update(dt);
+            game.update(self, dt);
+            game.render(self);
+
+            self.input.begin_frame();
             self.window.on_update();
+
+            let events: Vec<_> = self.window_events.try_iter().collect();
+
+            for mut event in events {
+                self.on_event(event.as_mut());
+            }
+
+            if let Err(error) = self.event_bus.flush() {
+                log::error!("{}", error);
+            }
+        }
+
+        self.shutdown(game)
+    }
+
+    fn shutdown<G: Game + ?Sized>(&mut self, game: &mut G) -> i32 {
+        let exit_code = self.exit.get_exit_code();
+
+        log::info!("Shutting down with exit code {}", exit_code);
+
+        game.shutdown(self);
+        self.layer_stack.clear();
+
+        while let Some(hook) = self.shutdown_hooks.pop() {
+            hook();
+        }
+
+        exit_code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{subscribe_event, KeyCode, KeyPressedEvent, Propagation};
+    use crate::time::ManualClock;
+    use crate::window::HeadlessInput;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    struct TestGame {
+        clock: ManualClock,
+        frames: u32,
+        frame_times: Vec<u32>,
+        a_pressed: Vec<bool>,
+        exit_after: u32,
+    }
+
+    impl TestGame {
+        fn new(clock: &ManualClock, exit_after: u32) -> Self {
+            Self {
+                clock: clock.clone(),
+                frames: 0,
+                frame_times: vec![],
+                a_pressed: vec![],
+                exit_after,
+            }
+        }
+    }
+
+    impl Game for TestGame {
+        fn update(&mut self, app: &mut Application, dt: Timestep) {
+            self.frames += 1;
+            self.frame_times.push(dt.get_milliseconds().round() as u32);
+            self.a_pressed
+                .push(app.get_input().is_key_pressed(KeyCode::A));
+            self.clock.advance(Duration::from_millis(10));
+
+            if self.frames == self.exit_after {
+                app.request_exit(3);
+            }
+        }
+    }
+
+    /// Keeps the window open, like an unsaved changes prompt would.
+    struct KeepOpenLayer;
+
+    impl Layer for KeepOpenLayer {
+        fn get_name(&self) -> &str {
+            "KeepOpenLayer"
+        }
+
+        fn on_event(&mut self, event: &mut dyn Event) {
+            if WindowCloseEvent::from_event_mut(event).is_some() {
+                event.set_handled(true);
+            }
         }
     }
+
+    fn headless_app(input: &HeadlessInput, clock: &ManualClock) -> Application {
+        let mut app = Application::with_window(
+            WindowBuilder::new(WindowProps::default())
+                .backend(WindowBackend::Headless(input.clone()))
+                .build(),
+        );
+
+        app.set_clock(Box::new(clock.clone()));
+        app
+    }
+
+    /// Counts the `E` events reaching the bus of `app`.
+    fn count_events<E: Event>(app: &Application) -> Arc<AtomicUsize> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let handler_count = count.clone();
+
+        subscribe_event(
+            app.get_event_bus().get_name(),
+            move |_: &mut E| {
+                handler_count.fetch_add(1, Ordering::SeqCst);
+            },
+            0,
+        )
+        .unwrap();
+
+        count
+    }
+
+    #[test]
+    fn runs_frames_and_delivers_events() {
+        let clock = ManualClock::new();
+        let input = HeadlessInput::default();
+        let mut app = headless_app(&input, &clock);
+        let keys = count_events::<KeyPressedEvent>(&app);
+
+        input.push(KeyPressedEvent::new(KeyCode::A, 0));
+        input.push_at(2, KeyPressedEvent::new(KeyCode::B, 0));
+
+        let mut game = TestGame::new(&clock, 5);
+
+        app.run(&mut game);
+        assert_eq!(game.frame_times, vec![0, 10, 10, 10, 10]);
+        assert_eq!(keys.load(Ordering::SeqCst), 2);
+        // Events reach the input state after the update of the frame they
+        // were delivered on.
+        assert_eq!(game.a_pressed, vec![false, true, false, false, false]);
+        assert!(app.get_input().is_key_down(KeyCode::A));
+        assert_eq!(app.get_frame_stats().get_frame_count(), 5);
+    }
+
+    #[test]
+    fn exit_code_is_returned() {
+        let clock = ManualClock::new();
+        let mut app = headless_app(&HeadlessInput::default(), &clock);
+        let mut game = TestGame::new(&clock, 2);
+
+        assert_eq!(app.run(&mut game), 3);
+        assert_eq!(game.frames, 2);
+    }
+
+    #[test]
+    fn window_close_exits() {
+        let clock = ManualClock::new();
+        let input = HeadlessInput::default();
+        let mut app = headless_app(&input, &clock);
+        let closes = count_events::<WindowCloseEvent>(&app);
+        let mut game = TestGame::new(&clock, 100);
+
+        input.push_at(2, WindowCloseEvent::new());
+
+        assert_eq!(app.run(&mut game), 0);
+        assert_eq!(game.frames, 2);
+        assert_eq!(closes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handled_window_close_is_cancelled() {
+        let clock = ManualClock::new();
+        let input = HeadlessInput::default();
+        let mut app = headless_app(&input, &clock);
+        let closes = count_events::<WindowCloseEvent>(&app);
+        let mut game = TestGame::new(&clock, 4);
+
+        app.push_layer(KeepOpenLayer);
+        input.push_at(2, WindowCloseEvent::new());
+
+        // A layer handling the close cancels it before the bus sees it.
+        assert_eq!(app.run(&mut game), 3);
+        assert_eq!(game.frames, 4);
+        assert_eq!(closes.load(Ordering::SeqCst), 0);
+
+        let input = HeadlessInput::default();
+        let mut app = headless_app(&input, &clock);
+        let mut game = TestGame::new(&clock, 4);
+
+        subscribe_event(
+            app.get_event_bus().get_name(),
+            |_: &mut WindowCloseEvent| Propagation::Stop,
+            0,
+        )
+        .unwrap();
+        input.push_at(2, WindowCloseEvent::new());
+
+        assert_eq!(app.run(&mut game), 3);
+        assert_eq!(game.frames, 4);
+    }
+
+    #[test]
+    fn shutdown_hooks_run_in_reverse_order() {
+        let clock = ManualClock::new();
+        let mut app = headless_app(&HeadlessInput::default(), &clock);
+        let hooks = Rc::new(RefCell::new(vec![]));
+
+        for name in &["first", "second"] {
+            let hooks = hooks.clone();
+            app.add_shutdown_hook(move || hooks.borrow_mut().push(*name));
+        }
+
+        app.run(&mut TestGame::new(&clock, 1));
+        assert_eq!(*hooks.borrow(), vec!["second", "first"]);
+    }
+
+    #[test]
+    fn applications_have_their_own_bus() {
+        let clock = ManualClock::new();
+        let first = headless_app(&HeadlessInput::default(), &clock);
+        let second = headless_app(&HeadlessInput::default(), &clock);
+
+        assert_ne!(
+            first.get_event_bus().get_name(),
+            second.get_event_bus().get_name()
+        );
+    }
 }
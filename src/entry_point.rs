@@ -1,23 +1,40 @@
 use crate::application::Application;
+use crate::game::Game;
 use crate::log;
-use std::error::Error;
-use crate::events::{Event, KeyPressedEvent, EventBus, dispatch_event, subscribe_event};
+use crate::window::WindowBuilder;
 
-pub struct CreateApplication;
+/// Defines `main` for a client crate: `nain::main!(MyGame);` runs a
+/// `MyGame::default()` and exits the process with its exit code.
+#[macro_export]
+macro_rules! main {
+    ($game:ty) => {
+        fn main() {
+            let game: $game = ::std::default::Default::default();
 
-impl CreateApplication {
-    pub fn new(application: &mut Application) -> Result<(), Box<dyn Error>> {
-        log::init()?;
-        let event_bus = EventBus::new("nain_engine");
+            ::std::process::exit($crate::run(game));
+        }
+    };
+}
 
-        subscribe_event("nain_engine", nain, 0);
+/// Sets up logging and the window, then runs `game` until it exits. Returns
+/// the exit code.
+pub fn run<G: Game>(mut game: G) -> i32 {
+    match game.log_config().apply() {
+        Ok(()) => {}
+        // Embedders may install their own logger first.
+        Err(log::LogError::AlreadyInitialized) => {
+            log::debug!("Logger already initialized, keeping it");
+        }
+        Err(error) => {
+            eprintln!("Failed to initialize logger: {}", error);
+            return 1;
+        }
+    }
 
-        application.run();
+    let window = WindowBuilder::new(game.window_props())
+        .backend(game.window_backend())
+        .build();
+    let mut application = Application::with_window(window);
 
-        Ok(())
-    }
+    application.run(&mut game)
 }
-
-fn nain(event: &mut dyn Event) {
-    println!("hahahahahahahah {}", event);
-}
\ No newline at end of file
@@ -0,0 +1,26 @@
+use crate::application::Application;
+use crate::log::LogConfig;
+use crate::time::Timestep;
+use crate::window::{WindowBackend, WindowProps};
+
+/// Implemented by the client to hook into the engine. Every callback gets the
+/// running `Application` so it can push layers or request an exit.
+pub trait Game {
+    fn window_props(&self) -> WindowProps {
+        WindowProps::default()
+    }
+
+    fn window_backend(&self) -> WindowBackend {
+        WindowBackend::from_env()
+    }
+
+    fn log_config(&self) -> LogConfig {
+        LogConfig::from_env()
+    }
+
+    fn init(&mut self, _app: &mut Application) {}
+    fn fixed_update(&mut self, _app: &mut Application, _step: Timestep) {}
+    fn update(&mut self, _app: &mut Application, _dt: Timestep) {}
+    fn render(&mut self, _app: &mut Application) {}
+    fn shutdown(&mut self, _app: &mut Application) {}
+}
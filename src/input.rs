@@ -0,0 +1,313 @@
+use crate::events::{
+    AsEvent, Event, GamepadAxis, GamepadAxisMovedEvent, GamepadButton, GamepadButtonPressedEvent,
+    GamepadButtonReleasedEvent, GamepadConnectedEvent, GamepadDisconnectedEvent, GamepadEvent,
+    KeyCode, KeyEvent, KeyPressedEvent, KeyReleasedEvent, MouseButton, MouseButtonEvent,
+    MouseButtonPressedEvent, MouseButtonReleasedEvent, MouseMovedEvent, MouseScrolledEvent,
+    WindowLostFocusEvent,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Keyboard, mouse and gamepad state as of the current frame, built from
+/// the window events. Clones share the same state, so layers can keep one
+/// from `Application::get_input` and query it during their updates.
+/// Gamepad events are ignored until their gamepad's connected event.
+#[derive(Clone, Default)]
+pub struct Input {
+    state: Arc<RwLock<InputState>>,
+}
+
+#[derive(Default)]
+struct InputState {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    mouse_position: (f64, f64),
+    mouse_delta: (f64, f64),
+    scroll: (f64, f64),
+    gamepads: BTreeMap<u32, GamepadState>,
+}
+
+#[derive(Default)]
+struct GamepadState {
+    name: String,
+    buttons_down: HashSet<GamepadButton>,
+    buttons_pressed: HashSet<GamepadButton>,
+    buttons_released: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `key_code` is held down.
+    pub fn is_key_down(&self, key_code: KeyCode) -> bool {
+        self.read().keys_down.contains(&key_code)
+    }
+
+    /// Whether `key_code` went down since the previous frame. Key repeats do
+    /// not count.
+    pub fn is_key_pressed(&self, key_code: KeyCode) -> bool {
+        self.read().keys_pressed.contains(&key_code)
+    }
+
+    /// Whether `key_code` went up since the previous frame.
+    pub fn is_key_released(&self, key_code: KeyCode) -> bool {
+        self.read().keys_released.contains(&key_code)
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.read().buttons_down.contains(&button)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.read().buttons_pressed.contains(&button)
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.read().buttons_released.contains(&button)
+    }
+
+    pub fn get_mouse_position(&self) -> (f64, f64) {
+        self.read().mouse_position
+    }
+
+    /// Mouse motion since the previous frame.
+    pub fn get_mouse_delta(&self) -> (f64, f64) {
+        self.read().mouse_delta
+    }
+
+    /// Scroll offset accumulated since the previous frame.
+    pub fn get_scroll(&self) -> (f64, f64) {
+        self.read().scroll
+    }
+
+    /// Connected gamepads, in ascending order.
+    pub fn get_gamepads(&self) -> Vec<u32> {
+        self.read().gamepads.keys().copied().collect()
+    }
+
+    pub fn is_gamepad_connected(&self, gamepad: u32) -> bool {
+        self.read().gamepads.contains_key(&gamepad)
+    }
+
+    pub fn get_gamepad_name(&self, gamepad: u32) -> Option<String> {
+        self.read()
+            .gamepads
+            .get(&gamepad)
+            .map(|state| state.name.clone())
+    }
+
+    pub fn is_gamepad_button_down(&self, gamepad: u32, button: GamepadButton) -> bool {
+        self.read()
+            .gamepads
+            .get(&gamepad)
+            .is_some_and(|state| state.buttons_down.contains(&button))
+    }
+
+    pub fn is_gamepad_button_pressed(&self, gamepad: u32, button: GamepadButton) -> bool {
+        self.read()
+            .gamepads
+            .get(&gamepad)
+            .is_some_and(|state| state.buttons_pressed.contains(&button))
+    }
+
+    pub fn is_gamepad_button_released(&self, gamepad: u32, button: GamepadButton) -> bool {
+        self.read()
+            .gamepads
+            .get(&gamepad)
+            .is_some_and(|state| state.buttons_released.contains(&button))
+    }
+
+    /// Last reported position of `axis`, or its rest value if it has not
+    /// moved.
+    pub fn get_gamepad_axis(&self, gamepad: u32, axis: GamepadAxis) -> f32 {
+        self.read()
+            .gamepads
+            .get(&gamepad)
+            .and_then(|state| state.axes.get(&axis).copied())
+            .unwrap_or_else(|| axis.get_rest_value())
+    }
+
+    /// Forgets what happened during the previous frame, before the window
+    /// delivers the events of the next one.
+    pub(crate) fn begin_frame(&self) {
+        let mut state = self.write();
+
+        state.keys_pressed.clear();
+        state.keys_released.clear();
+        state.buttons_pressed.clear();
+        state.buttons_released.clear();
+        for gamepad in state.gamepads.values_mut() {
+            gamepad.buttons_pressed.clear();
+            gamepad.buttons_released.clear();
+        }
+        state.mouse_delta = (0.0, 0.0);
+        state.scroll = (0.0, 0.0);
+    }
+
+    pub(crate) fn on_event(&self, event: &mut dyn Event) {
+        let mut state = self.write();
+
+        if let Some(event) = KeyPressedEvent::from_event_mut(event) {
+            let key_code = event.get_key_code();
+
+            if state.keys_down.insert(key_code) {
+                state.keys_pressed.insert(key_code);
+            }
+        } else if let Some(event) = KeyReleasedEvent::from_event_mut(event) {
+            let key_code = event.get_key_code();
+
+            state.keys_down.remove(&key_code);
+            state.keys_released.insert(key_code);
+        } else if let Some(event) = MouseButtonPressedEvent::from_event_mut(event) {
+            let button = event.get_mouse_button();
+
+            if state.buttons_down.insert(button) {
+                state.buttons_pressed.insert(button);
+            }
+        } else if let Some(event) = MouseButtonReleasedEvent::from_event_mut(event) {
+            let button = event.get_mouse_button();
+
+            state.buttons_down.remove(&button);
+            state.buttons_released.insert(button);
+        } else if let Some(event) = MouseMovedEvent::from_event_mut(event) {
+            state.mouse_position = (event.get_x(), event.get_y());
+            state.mouse_delta.0 += event.get_x_delta();
+            state.mouse_delta.1 += event.get_y_delta();
+        } else if let Some(event) = MouseScrolledEvent::from_event_mut(event) {
+            state.scroll.0 += event.get_x_offset();
+            state.scroll.1 += event.get_y_offset();
+        } else if let Some(event) = GamepadConnectedEvent::from_event_mut(event) {
+            state.gamepads.insert(
+                event.get_gamepad(),
+                GamepadState {
+                    name: event.get_gamepad_name().to_string(),
+                    ..GamepadState::default()
+                },
+            );
+        } else if let Some(event) = GamepadDisconnectedEvent::from_event_mut(event) {
+            state.gamepads.remove(&event.get_gamepad());
+        } else if let Some(event) = GamepadButtonPressedEvent::from_event_mut(event) {
+            if let Some(gamepad) = state.gamepads.get_mut(&event.get_gamepad()) {
+                if gamepad.buttons_down.insert(event.get_button()) {
+                    gamepad.buttons_pressed.insert(event.get_button());
+                }
+            }
+        } else if let Some(event) = GamepadButtonReleasedEvent::from_event_mut(event) {
+            if let Some(gamepad) = state.gamepads.get_mut(&event.get_gamepad()) {
+                gamepad.buttons_down.remove(&event.get_button());
+                gamepad.buttons_released.insert(event.get_button());
+            }
+        } else if let Some(event) = GamepadAxisMovedEvent::from_event_mut(event) {
+            if let Some(gamepad) = state.gamepads.get_mut(&event.get_gamepad()) {
+                gamepad.axes.insert(event.get_axis(), event.get_value());
+            }
+        } else if WindowLostFocusEvent::from_event_mut(event).is_some() {
+            // Releases are not reported to unfocused windows, so nothing
+            // would clear these otherwise.
+            state.keys_down.clear();
+            state.buttons_down.clear();
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, InputState> {
+        self.state.read().expect("Failed to read input state")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, InputState> {
+        self.state.write().expect("Failed to write input state")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_keys_across_frames() {
+        let input = Input::new();
+
+        input.begin_frame();
+        input.on_event(&mut KeyPressedEvent::new(KeyCode::W, 0));
+        assert!(input.is_key_down(KeyCode::W));
+        assert!(input.is_key_pressed(KeyCode::W));
+
+        input.begin_frame();
+        input.on_event(&mut KeyPressedEvent::new(KeyCode::W, 1));
+        assert!(input.is_key_down(KeyCode::W));
+        assert!(!input.is_key_pressed(KeyCode::W));
+
+        input.begin_frame();
+        input.on_event(&mut KeyReleasedEvent::new(KeyCode::W));
+        assert!(!input.is_key_down(KeyCode::W));
+        assert!(input.is_key_released(KeyCode::W));
+
+        input.begin_frame();
+        assert!(!input.is_key_released(KeyCode::W));
+    }
+
+    #[test]
+    fn tracks_mouse_state() {
+        let input = Input::new();
+
+        input.on_event(&mut MouseButtonPressedEvent::new(MouseButton::Left));
+        input.on_event(&mut MouseMovedEvent::with_delta(10.0, 20.0, 2.0, 3.0));
+        input.on_event(&mut MouseMovedEvent::with_delta(11.0, 22.0, 1.0, 2.0));
+        input.on_event(&mut MouseScrolledEvent::new(0.0, 1.0));
+        input.on_event(&mut MouseScrolledEvent::new(0.0, 2.0));
+
+        assert!(input.is_mouse_button_down(MouseButton::Left));
+        assert!(input.is_mouse_button_pressed(MouseButton::Left));
+        assert_eq!(input.get_mouse_position(), (11.0, 22.0));
+        assert_eq!(input.get_mouse_delta(), (3.0, 5.0));
+        assert_eq!(input.get_scroll(), (0.0, 3.0));
+
+        input.begin_frame();
+        assert!(!input.is_mouse_button_pressed(MouseButton::Left));
+        assert_eq!(input.get_mouse_position(), (11.0, 22.0));
+        assert_eq!(input.get_mouse_delta(), (0.0, 0.0));
+        assert_eq!(input.get_scroll(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let input = Input::new();
+
+        input.on_event(&mut KeyPressedEvent::new(KeyCode::A, 0));
+        input.on_event(&mut MouseButtonPressedEvent::new(MouseButton::Right));
+        input.on_event(&mut WindowLostFocusEvent::new());
+
+        assert!(!input.is_key_down(KeyCode::A));
+        assert!(!input.is_mouse_button_down(MouseButton::Right));
+    }
+
+    #[test]
+    fn tracks_gamepads() {
+        let input = Input::new();
+
+        input.on_event(&mut GamepadButtonPressedEvent::new(1, GamepadButton::A));
+        input.on_event(&mut GamepadConnectedEvent::new(2, "Pad"));
+        input.on_event(&mut GamepadButtonPressedEvent::new(2, GamepadButton::Start));
+        input.on_event(&mut GamepadAxisMovedEvent::new(2, GamepadAxis::LeftX, 0.75));
+
+        assert_eq!(input.get_gamepads(), vec![2]);
+        assert_eq!(input.get_gamepad_name(2).as_deref(), Some("Pad"));
+        assert!(input.is_gamepad_button_pressed(2, GamepadButton::Start));
+        assert_eq!(input.get_gamepad_axis(2, GamepadAxis::LeftX), 0.75);
+
+        input.begin_frame();
+        input.on_event(&mut WindowLostFocusEvent::new());
+        assert!(input.is_gamepad_button_down(2, GamepadButton::Start));
+        assert!(!input.is_gamepad_button_pressed(2, GamepadButton::Start));
+
+        input.on_event(&mut GamepadDisconnectedEvent::new(2));
+        assert!(!input.is_gamepad_connected(2));
+        assert!(!input.is_gamepad_button_down(2, GamepadButton::Start));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::events::{GamepadAxis, GamepadButton, KeyCode, MouseButton};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// A single physical input an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum InputSource {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    /// The button on any connected gamepad.
+    GamepadButton(GamepadButton),
+}
+
+/// Inputs that all have to be held for a binding to trigger, e.g.
+/// `LeftControl` + `S`.
+pub type Chord = Vec<InputSource>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AxisBinding {
+    /// `-1.0` while `negative` is held, `1.0` while `positive` is.
+    Buttons {
+        negative: InputSource,
+        positive: InputSource,
+    },
+    /// The position of an analog axis on any connected gamepad. Triggers are
+    /// rescaled to go from `0.0` at rest to `1.0`.
+    Gamepad {
+        #[serde(with = "gamepad_axis_name")]
+        axis: GamepadAxis,
+        #[serde(default)]
+        inverted: bool,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Axis {
+    /// Values no further from zero than this are reported as zero.
+    #[serde(default)]
+    pub dead_zone: f32,
+    pub bindings: Vec<AxisBinding>,
+}
+
+/// Named actions and axes with the inputs bound to them. This is the part of
+/// the input setup that is saved, see `ActionLayer` for turning input into
+/// `ActionEvent`s.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<Chord>>,
+    #[serde(default)]
+    axes: BTreeMap<String, Axis>,
+}
+
+#[derive(Debug)]
+pub enum InputMapError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for InputMapError {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputMapError::Io(error) => write!(formater, "Cannot access input map: {}", error),
+            InputMapError::Parse(error) => write!(formater, "Invalid input map: {}", error),
+            InputMapError::Serialize(error) => {
+                write!(formater, "Cannot serialize input map: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for InputMapError {}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputSource::Key(key_code) => write!(formater, "{}", key_code),
+            InputSource::MouseButton(button) => write!(formater, "Mouse{}", button),
+            InputSource::GamepadButton(button) => write!(formater, "Gamepad{}", button),
+        }
+    }
+}
+
+impl TryFrom<String> for InputSource {
+    type Error = String;
+
+    /// Parses key names like `Space`, or mouse and gamepad buttons prefixed
+    /// with `Mouse` or `Gamepad`, like `MouseLeft` or `GamepadA`.
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if let Some(button) = name.strip_prefix("Mouse") {
+            if let Ok(button) = button.parse() {
+                return Ok(InputSource::MouseButton(button));
+            }
+        }
+
+        if let Some(button) = name.strip_prefix("Gamepad") {
+            if let Ok(button) = button.parse() {
+                return Ok(InputSource::GamepadButton(button));
+            }
+        }
+
+        name.parse()
+            .map(InputSource::Key)
+            .map_err(|_| format!("Unknown input: '{}'", name))
+    }
+}
+
+impl From<InputSource> for String {
+    fn from(source: InputSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, InputMapError> {
+        let text = fs::read_to_string(path).map_err(InputMapError::Io)?;
+
+        Self::from_toml(&text)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), InputMapError> {
+        fs::write(path, self.to_toml()?).map_err(InputMapError::Io)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, InputMapError> {
+        toml::from_str(text).map_err(InputMapError::Parse)
+    }
+
+    pub fn to_toml(&self) -> Result<String, InputMapError> {
+        toml::to_string(self).map_err(InputMapError::Serialize)
+    }
+
+    /// Adds `chord` as another way to trigger `action`.
+    pub fn bind_action(&mut self, action: &str, chord: &[InputSource]) {
+        self.actions
+            .entry(action.to_string())
+            .or_default()
+            .push(chord.to_vec());
+    }
+
+    /// Replaces every binding of `action` with `chord`.
+    pub fn rebind_action(&mut self, action: &str, chord: &[InputSource]) {
+        self.actions
+            .insert(action.to_string(), vec![chord.to_vec()]);
+    }
+
+    pub fn unbind_action(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn get_action(&self, action: &str) -> Option<&[Chord]> {
+        self.actions.get(action).map(Vec::as_slice)
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, negative: InputSource, positive: InputSource) {
+        self.axes
+            .entry(axis.to_string())
+            .or_default()
+            .bindings
+            .push(AxisBinding::Buttons { negative, positive });
+    }
+
+    pub fn bind_gamepad_axis(&mut self, axis: &str, gamepad_axis: GamepadAxis, inverted: bool) {
+        self.axes
+            .entry(axis.to_string())
+            .or_default()
+            .bindings
+            .push(AxisBinding::Gamepad {
+                axis: gamepad_axis,
+                inverted,
+            });
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    pub fn set_dead_zone(&mut self, axis: &str, dead_zone: f32) {
+        self.axes.entry(axis.to_string()).or_default().dead_zone = dead_zone;
+    }
+
+    pub fn get_axis(&self, axis: &str) -> Option<&Axis> {
+        self.axes.get(axis)
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Chord])> {
+        self.actions
+            .iter()
+            .map(|(action, chords)| (action.as_str(), chords.as_slice()))
+    }
+
+    pub fn axes(&self) -> impl Iterator<Item = (&str, &Axis)> {
+        self.axes
+            .iter()
+            .map(|(axis, bindings)| (axis.as_str(), bindings))
+    }
+}
+
+/// Stores gamepad axes by name, like the inputs.
+mod gamepad_axis_name {
+    use crate::events::GamepadAxis;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(axis: &GamepadAxis, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(axis.get_name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<GamepadAxis, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_input_names() {
+        assert_eq!(
+            InputSource::try_from("Space".to_string()),
+            Ok(InputSource::Key(KeyCode::Space))
+        );
+        assert_eq!(
+            InputSource::try_from("MouseLeft".to_string()),
+            Ok(InputSource::MouseButton(MouseButton::Left))
+        );
+        assert_eq!(
+            InputSource::try_from("GamepadDpadUp".to_string()),
+            Ok(InputSource::GamepadButton(GamepadButton::DpadUp))
+        );
+        assert!(InputSource::try_from("MouseWheel".to_string()).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut map = InputMap::new();
+        map.bind_action("Jump", &[InputSource::Key(KeyCode::Space)]);
+        map.bind_action(
+            "Save",
+            &[
+                InputSource::Key(KeyCode::LeftControl),
+                InputSource::Key(KeyCode::S),
+            ],
+        );
+        map.bind_action("Fire", &[InputSource::MouseButton(MouseButton::Left)]);
+        map.bind_axis(
+            "MoveX",
+            InputSource::Key(KeyCode::A),
+            InputSource::Key(KeyCode::D),
+        );
+        map.bind_gamepad_axis("MoveX", GamepadAxis::LeftX, false);
+        map.bind_gamepad_axis("MoveY", GamepadAxis::LeftY, true);
+        map.set_dead_zone("MoveX", 0.2);
+        map.bind_action("Jump", &[InputSource::GamepadButton(GamepadButton::A)]);
+
+        let text = map.to_toml().unwrap();
+
+        assert!(
+            text.contains(r#"Save = [["LeftControl", "S"]]"#),
+            "{}",
+            text
+        );
+        assert!(text.contains(r#"axis = "LeftY""#), "{}", text);
+        assert_eq!(InputMap::from_toml(&text).unwrap(), map);
+    }
+
+    #[test]
+    fn rebinding_replaces_every_chord() {
+        let mut map = InputMap::from_toml(
+            r#"
+            [actions]
+            Jump = [["Space"], ["W"]]
+            "#,
+        )
+        .unwrap();
+
+        map.rebind_action("Jump", &[InputSource::Key(KeyCode::J)]);
+
+        assert_eq!(
+            map.get_action("Jump"),
+            Some(&[vec![InputSource::Key(KeyCode::J)]][..])
+        );
+        assert!(InputMap::from_toml("[actions]\nJump = [[\"Nope\"]]").is_err());
+    }
+}
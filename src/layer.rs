@@ -0,0 +1,16 @@
+use crate::events::Event;
+use crate::time::Timestep;
+
+/// A slice of the application, like game logic, a debug overlay or UI, that
+/// the `LayerStack` updates and feeds events to.
+pub trait Layer {
+    fn get_name(&self) -> &str {
+        "Layer"
+    }
+
+    fn on_attach(&mut self) {}
+    fn on_detach(&mut self) {}
+    fn on_update(&mut self, _dt: Timestep) {}
+    fn on_fixed_update(&mut self, _step: Timestep) {}
+    fn on_event(&mut self, _event: &mut dyn Event) {}
+}
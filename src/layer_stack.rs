@@ -0,0 +1,258 @@
+use crate::events::Event;
+use crate::layer::Layer;
+use crate::log;
+use crate::time::Timestep;
+
+/// Layers ordered bottom to top, with overlays always above regular layers.
+/// Updates run bottom-up and events propagate top-down until one of the
+/// layers marks them handled.
+#[derive(Default)]
+pub struct LayerStack {
+    layers: Vec<Box<dyn Layer>>,
+    layer_insert_index: usize,
+}
+
+impl LayerStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches `layer` on top of the regular layers, below every overlay.
+    pub fn push_layer(&mut self, mut layer: Box<dyn Layer>) {
+        log::debug!("Attaching layer: {}", layer.get_name());
+
+        layer.on_attach();
+        self.layers.insert(self.layer_insert_index, layer);
+        self.layer_insert_index += 1;
+    }
+
+    /// Attaches `overlay` on top of the stack.
+    pub fn push_overlay(&mut self, mut overlay: Box<dyn Layer>) {
+        log::debug!("Attaching overlay: {}", overlay.get_name());
+
+        overlay.on_attach();
+        self.layers.push(overlay);
+    }
+
+    /// Detaches and returns the topmost regular layer.
+    pub fn pop_layer(&mut self) -> Option<Box<dyn Layer>> {
+        if self.layer_insert_index == 0 {
+            return None;
+        }
+
+        self.layer_insert_index -= 1;
+        let layer = self.layers.remove(self.layer_insert_index);
+
+        Some(Self::detach(layer))
+    }
+
+    /// Detaches and returns the topmost overlay.
+    pub fn pop_overlay(&mut self) -> Option<Box<dyn Layer>> {
+        if self.layers.len() == self.layer_insert_index {
+            return None;
+        }
+
+        self.layers.pop().map(Self::detach)
+    }
+
+    /// Detaches every layer, from the top down.
+    pub fn clear(&mut self) {
+        while let Some(layer) = self.layers.pop() {
+            Self::detach(layer);
+        }
+
+        self.layer_insert_index = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Iterates from the bottom layer to the top overlay.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &dyn Layer> {
+        self.layers.iter().map(|layer| layer.as_ref())
+    }
+
+    pub fn on_update(&mut self, dt: Timestep) {
+        for layer in self.layers.iter_mut() {
+            layer.on_update(dt);
+        }
+    }
+
+    pub fn on_fixed_update(&mut self, step: Timestep) {
+        for layer in self.layers.iter_mut() {
+            layer.on_fixed_update(step);
+        }
+    }
+
+    pub fn on_event(&mut self, event: &mut dyn Event) {
+        for layer in self.layers.iter_mut().rev() {
+            if event.is_handled() {
+                break;
+            }
+
+            layer.on_event(event);
+        }
+    }
+
+    fn detach(mut layer: Box<dyn Layer>) -> Box<dyn Layer> {
+        log::debug!("Detaching layer: {}", layer.get_name());
+
+        layer.on_detach();
+        layer
+    }
+}
+
+impl Drop for LayerStack {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{KeyCode, KeyPressedEvent};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Calls = Rc<RefCell<Vec<String>>>;
+
+    struct TestLayer {
+        name: &'static str,
+        calls: Calls,
+        consume: bool,
+    }
+
+    impl TestLayer {
+        fn boxed(name: &'static str, calls: &Calls) -> Box<dyn Layer> {
+            Box::new(Self {
+                name,
+                calls: calls.clone(),
+                consume: false,
+            })
+        }
+
+        fn record(&self, call: &str) {
+            self.calls
+                .borrow_mut()
+                .push(format!("{} {}", self.name, call));
+        }
+    }
+
+    impl Layer for TestLayer {
+        fn get_name(&self) -> &str {
+            self.name
+        }
+
+        fn on_attach(&mut self) {
+            self.record("attach");
+        }
+
+        fn on_detach(&mut self) {
+            self.record("detach");
+        }
+
+        fn on_update(&mut self, _dt: Timestep) {
+            self.record("update");
+        }
+
+        fn on_event(&mut self, event: &mut dyn Event) {
+            self.record("event");
+
+            if self.consume {
+                event.set_handled(true);
+            }
+        }
+    }
+
+    fn names(stack: &LayerStack) -> Vec<&str> {
+        stack.iter().map(|layer| layer.get_name()).collect()
+    }
+
+    #[test]
+    fn overlays_stay_above_layers() {
+        let calls = Calls::default();
+        let mut stack = LayerStack::new();
+
+        stack.push_overlay(TestLayer::boxed("debug", &calls));
+        stack.push_layer(TestLayer::boxed("world", &calls));
+        stack.push_overlay(TestLayer::boxed("ui", &calls));
+        stack.push_layer(TestLayer::boxed("player", &calls));
+
+        assert_eq!(names(&stack), vec!["world", "player", "debug", "ui"]);
+    }
+
+    #[test]
+    fn updates_run_bottom_up_and_events_top_down() {
+        let calls = Calls::default();
+        let mut stack = LayerStack::new();
+
+        stack.push_layer(TestLayer::boxed("world", &calls));
+        stack.push_overlay(TestLayer::boxed("ui", &calls));
+        calls.borrow_mut().clear();
+
+        stack.on_update(Timestep::from_seconds(0.016));
+        stack.on_event(&mut KeyPressedEvent::new(KeyCode::A, 0));
+
+        assert_eq!(
+            *calls.borrow(),
+            vec!["world update", "ui update", "ui event", "world event"]
+        );
+    }
+
+    #[test]
+    fn handled_events_stop_propagating() {
+        let calls = Calls::default();
+        let mut stack = LayerStack::new();
+
+        stack.push_layer(TestLayer::boxed("world", &calls));
+        stack.push_overlay(Box::new(TestLayer {
+            name: "ui",
+            calls: calls.clone(),
+            consume: true,
+        }));
+        calls.borrow_mut().clear();
+
+        let mut event = KeyPressedEvent::new(KeyCode::A, 0);
+        stack.on_event(&mut event);
+
+        assert!(event.is_handled());
+        assert_eq!(*calls.borrow(), vec!["ui event"]);
+    }
+
+    #[test]
+    fn pop_detaches_the_topmost_layer_or_overlay() {
+        let calls = Calls::default();
+        let mut stack = LayerStack::new();
+
+        stack.push_layer(TestLayer::boxed("world", &calls));
+        stack.push_layer(TestLayer::boxed("player", &calls));
+        stack.push_overlay(TestLayer::boxed("ui", &calls));
+        calls.borrow_mut().clear();
+
+        assert_eq!(stack.pop_layer().unwrap().get_name(), "player");
+        assert_eq!(stack.pop_overlay().unwrap().get_name(), "ui");
+        assert!(stack.pop_overlay().is_none());
+        assert_eq!(names(&stack), vec!["world"]);
+        assert_eq!(*calls.borrow(), vec!["player detach", "ui detach"]);
+    }
+
+    #[test]
+    fn dropping_the_stack_detaches_top_down() {
+        let calls = Calls::default();
+        let mut stack = LayerStack::new();
+
+        stack.push_layer(TestLayer::boxed("world", &calls));
+        stack.push_overlay(TestLayer::boxed("ui", &calls));
+        calls.borrow_mut().clear();
+
+        drop(stack);
+
+        assert_eq!(*calls.borrow(), vec!["ui detach", "world detach"]);
+    }
+}
@@ -1,8 +1,24 @@
-pub use self::application::Application;
-pub use self::entry_point::CreateApplication;
+pub use self::action_layer::ActionLayer;
+pub use self::application::{Application, ExitHandle};
+pub use self::entry_point::run;
+pub use self::game::Game;
+pub use self::input::Input;
+pub use self::input_map::{Axis, AxisBinding, Chord, InputMap, InputMapError, InputSource};
+pub use self::layer::Layer;
+pub use self::layer_stack::LayerStack;
+pub use self::time::{
+    Clock, FixedTimestep, FrameStats, FrameTimer, ManualClock, SystemClock, Timestep, TimestepError,
+};
 pub use nain_log as log;
 pub use nain_events as events;
 pub use nain_window as window;
 
+mod action_layer;
 mod application;
 mod entry_point;
+mod game;
+mod input;
+mod input_map;
+mod layer;
+mod layer_stack;
+mod time;
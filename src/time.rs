@@ -0,0 +1,400 @@
+use crate::log;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time elapsed between two updates, in seconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Timestep(f32);
+
+impl Timestep {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(seconds)
+    }
+
+    pub fn get_seconds(&self) -> f32 {
+        self.0
+    }
+
+    pub fn get_milliseconds(&self) -> f32 {
+        self.0 * 1000.0
+    }
+}
+
+impl From<Duration> for Timestep {
+    fn from(duration: Duration) -> Self {
+        Self(duration.as_secs_f32())
+    }
+}
+
+impl From<Timestep> for f32 {
+    fn from(timestep: Timestep) -> Self {
+        timestep.0
+    }
+}
+
+impl fmt::Display for Timestep {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        write!(formater, "{:.3}ms", self.get_milliseconds())
+    }
+}
+
+/// Source of time for the main loop. Swap in a `ManualClock` to drive frames
+/// deterministically.
+pub trait Clock {
+    /// Time elapsed since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+/// Measures the time between frames using a `Clock`.
+pub struct FrameTimer {
+    clock: Box<dyn Clock>,
+    last_frame: Duration,
+}
+
+impl FrameTimer {
+    pub fn new(clock: Box<dyn Clock>) -> Self {
+        let last_frame = clock.now();
+
+        Self { clock, last_frame }
+    }
+
+    /// Starts measuring the next frame from now.
+    pub fn reset(&mut self) {
+        self.last_frame = self.clock.now();
+    }
+
+    /// Returns the time since the previous tick, or since the last reset.
+    pub fn tick(&mut self) -> Duration {
+        let now = self.clock.now();
+        let frame_time = now.checked_sub(self.last_frame).unwrap_or_default();
+        self.last_frame = now;
+
+        frame_time
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TimestepError {
+    /// Zero, negative, infinite or NaN.
+    InvalidRate(f64),
+    /// Steps would be shorter than a nanosecond or too long for a `Duration`.
+    RateOutOfRange(f64),
+}
+
+impl fmt::Display for TimestepError {
+    fn fmt(&self, formater: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimestepError::InvalidRate(hz) => write!(
+                formater,
+                "Fixed update rate must be positive and finite, got {}",
+                hz
+            ),
+            TimestepError::RateOutOfRange(hz) => {
+                write!(formater, "Fixed update rate is out of range: {}", hz)
+            }
+        }
+    }
+}
+
+impl Error for TimestepError {}
+
+/// Splits variable frame times into fixed simulation steps.
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_nanos(1_000_000_000 / 60),
+            max_steps: 8,
+            accumulator: Duration::default(),
+        }
+    }
+}
+
+impl FixedTimestep {
+    pub fn new(hz: f64) -> Result<Self, TimestepError> {
+        let mut fixed_timestep = Self::default();
+        fixed_timestep.set_rate(hz)?;
+
+        Ok(fixed_timestep)
+    }
+
+    /// Leaves the rate unchanged if `hz` is not a usable rate.
+    pub fn set_rate(&mut self, hz: f64) -> Result<(), TimestepError> {
+        if !(hz > 0.0 && hz.is_finite()) {
+            return Err(TimestepError::InvalidRate(hz));
+        }
+
+        self.step = Duration::try_from_secs_f64(1.0 / hz)
+            .ok()
+            .filter(|step| *step > Duration::ZERO)
+            .ok_or(TimestepError::RateOutOfRange(hz))?;
+
+        Ok(())
+    }
+
+    pub fn get_rate(&self) -> f64 {
+        1.0 / self.step.as_secs_f64()
+    }
+
+    /// Caps how many steps a single frame may run to catch up. Time beyond the
+    /// cap is dropped so a slow frame cannot snowball into slower ones.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    pub fn get_max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn get_step(&self) -> Timestep {
+        self.step.into()
+    }
+
+    /// Adds `frame_time` and returns how many fixed steps to run this frame.
+    pub fn accumulate(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator += frame_time;
+
+        let mut steps = 0;
+
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if self.accumulator >= self.step {
+            let remainder =
+                Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64);
+
+            log::warn!(
+                "Fixed update fell behind, dropping {:?}",
+                self.accumulator - remainder
+            );
+
+            self.accumulator = remainder;
+        }
+
+        steps
+    }
+
+    /// How far the simulation is into the next step, from 0 to 1. Use it to
+    /// interpolate between the last two simulated states when rendering.
+    pub fn get_alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+}
+
+/// Rolling frame-time statistics over the last frames.
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(120)
+    }
+}
+
+impl FrameStats {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(frame_time);
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub fn get_min(&self) -> Duration {
+        self.frame_times.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn get_max(&self) -> Duration {
+        self.frame_times.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn get_average(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::default();
+        }
+
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    pub fn get_fps(&self) -> f32 {
+        let average = self.get_average().as_secs_f32();
+
+        if average > 0.0 {
+            1.0 / average
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn timestep_converts_units() {
+        let timestep = Timestep::from(millis(250));
+
+        assert_eq!(timestep.get_seconds(), 0.25);
+        assert_eq!(timestep.get_milliseconds(), 250.0);
+        assert_eq!(f32::from(timestep), 0.25);
+    }
+
+    #[test]
+    fn frame_timer_follows_the_clock() {
+        let clock = ManualClock::new();
+        let mut timer = FrameTimer::new(Box::new(clock.clone()));
+
+        clock.advance(millis(16));
+        assert_eq!(timer.tick(), millis(16));
+
+        clock.advance(millis(5));
+        clock.advance(millis(5));
+        assert_eq!(timer.tick(), millis(10));
+        assert_eq!(timer.tick(), Duration::default());
+
+        clock.advance(millis(100));
+        timer.reset();
+        clock.advance(millis(1));
+        assert_eq!(timer.tick(), millis(1));
+    }
+
+    #[test]
+    fn fixed_timestep_runs_whole_steps_and_keeps_the_remainder() {
+        let mut fixed = FixedTimestep::new(100.0).unwrap();
+
+        assert_eq!(fixed.accumulate(millis(25)), 2);
+        assert!((fixed.get_alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(fixed.accumulate(millis(5)), 1);
+        assert!(fixed.get_alpha().abs() < 1e-4);
+        assert_eq!(fixed.accumulate(millis(3)), 0);
+    }
+
+    #[test]
+    fn fixed_timestep_caps_catch_up_steps() {
+        let mut fixed = FixedTimestep::new(100.0).unwrap();
+        fixed.set_max_steps(4);
+
+        assert_eq!(fixed.accumulate(millis(1005)), 4);
+        assert!((fixed.get_alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(fixed.accumulate(millis(5)), 1);
+    }
+
+    #[test]
+    fn fixed_timestep_rate_is_configurable() {
+        let mut fixed = FixedTimestep::default();
+        assert!((fixed.get_rate() - 60.0).abs() < 1e-3);
+
+        fixed.set_rate(50.0).unwrap();
+        assert!((fixed.get_step().get_seconds() - 0.02).abs() < 1e-6);
+        assert_eq!(fixed.accumulate(millis(40)), 2);
+    }
+
+    #[test]
+    fn fixed_timestep_rejects_unusable_rates() {
+        for hz in &[0.0, -30.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                FixedTimestep::new(*hz),
+                Err(TimestepError::InvalidRate(_))
+            ));
+        }
+
+        let mut fixed = FixedTimestep::new(50.0).unwrap();
+
+        assert_eq!(
+            fixed.set_rate(1e-30),
+            Err(TimestepError::RateOutOfRange(1e-30))
+        );
+        assert_eq!(
+            fixed.set_rate(1e12),
+            Err(TimestepError::RateOutOfRange(1e12))
+        );
+        assert!((fixed.get_rate() - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn frame_stats_track_recent_frames() {
+        let mut stats = FrameStats::new(3);
+
+        assert_eq!(stats.get_fps(), 0.0);
+
+        for frame_time in &[40, 10, 20, 30] {
+            stats.record(millis(*frame_time));
+        }
+
+        assert_eq!(stats.get_frame_count(), 3);
+        assert_eq!(stats.get_min(), millis(10));
+        assert_eq!(stats.get_max(), millis(30));
+        assert_eq!(stats.get_average(), millis(20));
+        assert!((stats.get_fps() - 50.0).abs() < 1e-3);
+    }
+}